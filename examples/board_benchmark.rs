@@ -0,0 +1,107 @@
+//! Compare the time it takes to lay out and paint a 200x200 board, with the renderer which the painted board replaced
+//! and with the single painted board widget.
+//!
+//! The previous renderer is reproduced as `render_minefield` had it once the board could be zoomed and panned: one
+//! `Button` or `Label` per visible spot, each in its own `allocate_ui_at_rect`, and checked for clicks. It had already
+//! taken the place of the original `egui_extras::TableBuilder` grid, which isn't a dependency anymore.
+//!
+//! Run with `cargo run --release --example board_benchmark`
+
+use eframe::{
+    egui::{self, Button, CentralPanel, Context, Label, Layout, PointerButton, RawInput, RichText, ScrollArea, Sense},
+    epaint::{Color32, Pos2, Rect, Vec2},
+};
+use minesweep_rs::board::{Board, BoardLayout, SpotLook};
+use std::time::{Duration, Instant};
+
+const SIZE: u16 = 200;
+const SPOT_SIZE: f32 = 14.0;
+const FRAMES: u32 = 20;
+
+/// A board in the middle of a game: hidden spots, flags and numbers
+fn look(x: u16, y: u16) -> SpotLook {
+    match (x as usize * 7 + y as usize * 13) % 10 {
+        0..=3 => SpotLook::button(" ", Color32::GRAY),
+        4 => SpotLook::button("⚐", Color32::GREEN),
+        n => SpotLook::label(["1", "2", "3", "4", "5"][n - 5], Color32::WHITE),
+    }
+}
+
+/// The previous renderer: one widget per visible spot, each laid out in its own area
+fn previous_renderer(ctx: &Context) {
+    CentralPanel::default().show(ctx, |ui| {
+        let layout = BoardLayout { width: SIZE, height: SIZE, spot_size: SPOT_SIZE, spacing: ui.spacing().item_spacing };
+
+        ScrollArea::both().id_source("minefield").show_viewport(ui, |ui, viewport| {
+            let origin = ui.max_rect().min;
+            ui.allocate_rect(Rect::from_min_size(origin, layout.size()), Sense::hover());
+
+            let (xs, ys) = layout.visible_spots(viewport);
+            for y in ys {
+                for x in xs.clone() {
+                    let rect = layout.spot_rect(x, y).translate(origin.to_vec2());
+                    ui.allocate_ui_at_rect(rect, |ui| {
+                        ui.push_id((x, y), |ui| {
+                            ui.with_layout(Layout::centered_and_justified(egui::Direction::LeftToRight), |ui| {
+                                let look = look(x, y);
+                                let text = RichText::new(look.text).color(look.color).monospace().size(SPOT_SIZE);
+                                let _clicked = if look.raised {
+                                    let button = ui.add_enabled(true, Button::new(text));
+                                    button.clicked_by(PointerButton::Primary) || button.clicked_by(PointerButton::Secondary)
+                                } else {
+                                    ui.add_enabled(true, Label::new(text).sense(Sense::click())).clicked_by(PointerButton::Middle)
+                                };
+                            });
+                        });
+                    });
+                }
+            }
+        });
+    });
+}
+
+/// The whole board painted by a single widget
+fn painted_board(ctx: &Context) {
+    CentralPanel::default().show(ctx, |ui| {
+        let layout = BoardLayout { width: SIZE, height: SIZE, spot_size: SPOT_SIZE, spacing: ui.spacing().item_spacing };
+
+        ScrollArea::both().show_viewport(ui, |ui, viewport| {
+            Board::new(layout, look).show(ui, viewport);
+        });
+    });
+}
+
+/// Average time of a frame, including the tessellation of what was painted
+fn measure(run_ui: impl Fn(&Context)) -> Duration {
+    let ctx = Context::default();
+
+    // The whole board is in view
+    let input = || RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, Vec2::splat(SIZE as f32 * 40.0))),
+        ..Default::default()
+    };
+
+    // Warm up (fonts, layout caches)
+    for _ in 0..3 {
+        let output = ctx.run(input(), &run_ui);
+        ctx.tessellate(output.shapes);
+    }
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        let output = ctx.run(input(), &run_ui);
+        ctx.tessellate(output.shapes);
+    }
+
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    let previous = measure(previous_renderer);
+    let painted = measure(painted_board);
+
+    println!("{}x{} board, average over {} frames", SIZE, SIZE, FRAMES);
+    println!("  previous renderer: {:>8.2} ms", previous.as_secs_f64() * 1000.0);
+    println!("  painted board:     {:>8.2} ms", painted.as_secs_f64() * 1000.0);
+    println!("  speedup:           {:>8.1}x", previous.as_secs_f64() / painted.as_secs_f64());
+}
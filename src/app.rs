@@ -1,39 +1,89 @@
-use crate::minefield;
-
-use minefield::{Minefield, SpotState, StepResult, SpotKind};
+use crate::{game, session, clock, minefield, solver, save, board_formats, replay, rawvf, high_scores, stats, board, key_bindings};
+
+use save::SavedGame;
+use replay::{Recorder, ReplayAction, Replay, Player};
+use rawvf::ReplayFile;
+use high_scores::{HighScores, HighScore, ScoreBoard};
+use stats::Stats;
+use board::{Board, BoardLayout, SpotLook};
+use key_bindings::{KeyAction, KeyBindings};
+use game::{GameState, GameResult, GameConfig, GameDifficulty};
+use session::{GameSession, MoveOutcome};
+use minefield::{Minefield, Spot, SpotState, SpotKind, FirstStepPolicy};
 use eframe::{
-    egui::{PointerButton, self, Layout, Label, RichText, Button, Context, TextStyle, Ui, CentralPanel, Sense, Direction, TopBottomPanel, Window, ComboBox},
+    egui::{PointerButton, self, Event, Key, Layout, Label, RichText, Button, Context, TextStyle, Ui, CentralPanel, ScrollArea, Sense, TopBottomPanel, Window, ComboBox, TextEdit, Slider, CollapsingHeader, Grid},
     epaint::{Color32, Vec2},
     emath::{Align},
     Frame, App, CreationContext,
 };
-use egui_extras::{TableBuilder, Size};
-use serde::{Serialize, Deserialize};
-use std::sync::mpsc::{channel, Receiver};
-
-// Native timer
-#[cfg(not(target_arch = "wasm32"))]
-use timer::{Timer, Guard};
-
-// WASM timer
-#[cfg(target_arch = "wasm32")]
-use gloo_timers::callback::Interval;
+use std::time::Duration;
 
 pub struct MinesweepRsApp {
-    minefield: Minefield,
-    placed_flags: i32,
-    timer: AppTimer,
-    seconds_lapsed: i32,
-    game_state: GameState,
+    session: GameSession,
+    seed: u64,
+    seed_input: String,
+    game_result: Option<GameResult>,
     game_config: GameConfig,
     ui_toolbar_group: UiToolbarGroup,
+
+    /// Difficulty selected in the Settings window, which stays custom even if the board matches a standard one
+    settings_difficulty: GameDifficulty,
+
+    hint: Option<Hint>,
+    hints_used: u32,
+    game_file: String,
+    game_file_message: String,
+    board_text: String,
+    recorder: Recorder,
+    replay_viewer: Option<ReplayViewer>,
+    player_name: String,
+    started_at: String,
+    high_scores: HighScores,
+    high_score_rank: Option<usize>,
+    is_imported_board: bool,
+    stats: Stats,
+    is_stats_recorded: bool,
+    stats_message: String,
+
+    board_view: BoardView,
+
+    /// Board size (width, height) the native window was last sized for
+    window_board_size: Option<(u16, u16)>,
+
+    key_bindings: KeyBindings,
+
+    /// Action whose next key is being waited for, to bind it
+    key_capture: Option<KeyAction>,
+
+    /// Spot under the keyboard cursor, once the cursor was moved
+    cursor: Option<(u16, u16)>,
+    scroll_to_cursor: bool,
 }
 
 impl App for MinesweepRsApp {
     fn update(&mut self, ctx: &Context, frame: &mut Frame) {
-        ctx.request_repaint();
         ctx.set_debug_on_hover(false);
 
+        // The game waits while the player is away
+        if !ctx.input().raw.has_focus {
+            self.session.pause();
+        }
+
+        // Apart from input, only the running clock and playing replays need the UI to be repainted
+        if self.session.state() == GameState::Running && !self.session.is_paused() {
+            ctx.request_repaint_after(Self::CLOCK_REPAINT_INTERVAL);
+        }
+
+        if let Some(replay_viewer) = &mut self.replay_viewer {
+            replay_viewer.tick(ctx.input().time);
+
+            if replay_viewer.is_playing {
+                ctx.request_repaint();
+            }
+        }
+
+        self.handle_keys(ctx);
+        self.fit_window_to_board(frame);
         self.render_top_panel(ctx, frame);
         self.render_bottom_panel(ctx, frame);
         self.render_toolbar_group(ctx, frame);
@@ -42,13 +92,55 @@ impl App for MinesweepRsApp {
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
         eframe::set_value(storage, Self::APP_NAME, &self.game_config);
+        eframe::set_value(storage, Self::SAVED_GAME_KEY, &self.saved_game());
+        eframe::set_value(storage, Self::PLAYER_NAME_KEY, &self.player_name);
+        eframe::set_value(storage, Self::KEY_BINDINGS_KEY, &self.key_bindings);
+        eframe::set_value(storage, Self::HIGH_SCORES_KEY, &self.high_scores);
+        eframe::set_value(storage, Self::STATS_KEY, &self.stats);
     }
 }
 
 impl MinesweepRsApp {
     const APP_NAME: &str = "minesweep-rs";
+    const SAVED_GAME_KEY: &str = "minesweep-rs-game";
+    const PLAYER_NAME_KEY: &str = "minesweep-rs-player";
+    const KEY_BINDINGS_KEY: &str = "minesweep-rs-keys";
+    const HIGH_SCORES_KEY: &str = "minesweep-rs-high-scores";
+    const STATS_KEY: &str = "minesweep-rs-stats";
     const REFRESH_BTN_CHAR: &str = "🔄";
     const SETTINGS_BTN_CHAR: &str = "🛠";
+    const GAME_FILE_BTN_CHAR: &str = "💾";
+    const BEST_TIMES_BTN_CHAR: &str = "🏆";
+    const STATS_BTN_CHAR: &str = "📊";
+    const HISTOGRAM_BUCKETS: usize = 10;
+
+    /// How often the clock is redrawn while the game is running
+    const CLOCK_REPAINT_INTERVAL: Duration = Duration::from_millis(31);
+
+    /// Range of the spot size, which otherwise scales with the space available to the minefield
+    const MIN_SPOT_SIZE: f32 = 14.0;
+    const MAX_SPOT_SIZE: f32 = 48.0;
+
+    /// Spot size the native window is sized for, when the board is shown
+    const DEFAULT_SPOT_SIZE: f32 = 30.0;
+
+    /// Room taken by the top and bottom panels, and by the margins around the minefield
+    const PANELS_SIZE: Vec2 = Vec2::new(16.0, 150.0);
+    /// Length of the longer side of the minimap, and its distance from the corner of the board view
+    const MINIMAP_SIZE: f32 = 160.0;
+    const MINIMAP_MARGIN: f32 = 12.0;
+    const MINIMAP_BACKGROUND_COLOR: Color32 = Color32::from_black_alpha(200);
+    const MINIMAP_VIEW_COLOR: Color32 = Color32::YELLOW;
+
+    const MIN_WINDOW_SIZE: Vec2 = Vec2::new(480.0, 360.0);
+    const MAX_WINDOW_SIZE: Vec2 = Vec2::new(1600.0, 1000.0);
+    const COPY_BTN_CHAR: &str = "📋";
+    const HINT_BTN_CHAR: &str = "💡";
+    const UNDO_BTN_CHAR: &str = "↩";
+    const REDO_BTN_CHAR: &str = "↪";
+    const PLAY_BTN_CHAR: &str = "▶";
+    const PAUSE_BTN_CHAR: &str = "⏸";
+    const HINT_COLOR: Color32 = Color32::from_rgb(0, 92, 128);
     const ABOUT_BTN_CHAR: &str = "ℹ";
     const MINE_CAHR: &str = "☢";
     const MINE_COLOR: Color32 = Color32::RED;
@@ -73,31 +165,49 @@ impl MinesweepRsApp {
 
     pub fn with_context(mut self, cc: &CreationContext) -> Self {
         if let Some(storage) = cc.storage {
-            self.game_config = eframe::get_value(storage, Self::APP_NAME).unwrap_or_default();
+            // The stored config may have been edited by hand
+            self.game_config = eframe::get_value::<GameConfig>(storage, Self::APP_NAME).unwrap_or_default().validated();
+            self.player_name = eframe::get_value(storage, Self::PLAYER_NAME_KEY).unwrap_or_default();
+            self.key_bindings = eframe::get_value(storage, Self::KEY_BINDINGS_KEY).unwrap_or_default();
+            self.high_scores = eframe::get_value(storage, Self::HIGH_SCORES_KEY).unwrap_or_default();
+            self.stats = eframe::get_value(storage, Self::STATS_KEY).unwrap_or_default();
             tracing::debug!("Loaded config from storage {:?}", self.game_config);
         } else {
             tracing::debug!("No storage. Using default config {:?}", self.game_config);
         }
 
-        self.minefield = Minefield::new(self.game_config.width, self.game_config.height).with_mines(self.game_config.mines);
+        self.session = GameSession::new(self.game_config.minefield(self.seed));
+        self.start_recording();
+
+        // Continue the game which was being played when the app was closed
+        if let Some(storage) = cc.storage {
+            let saved_game = eframe::get_value::<SavedGame>(storage, Self::SAVED_GAME_KEY).map(SavedGame::validate);
+
+            match saved_game {
+                Some(Ok(saved_game)) => {
+                    tracing::debug!("Loaded game from storage");
+                    self.load_game(saved_game);
+                },
+                Some(Err(e)) => {
+                    tracing::debug!("Ignoring game from storage: {}", e);
+                },
+                None => {},
+            }
+        }
 
         self
     }
     
     #[allow(dead_code)]
     pub fn with_configs(mut self, game_config: GameConfig) -> Self {
-        self.game_config = game_config;
-        self.minefield = Minefield::new(self.game_config.width, self.game_config.height).with_mines(self.game_config.mines);
+        self.game_config = game_config.validated();
+        self.session = GameSession::new(self.game_config.minefield(self.seed));
+        self.start_recording();
 
         self
     }
 
     fn render_top_panel(&mut self, ctx: &Context, _: &mut Frame) {
-        // Service app timer
-        while self.timer.poll().is_some() {
-            self.seconds_lapsed += 1;
-        }
-
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.add_space(10.);
             egui::menu::bar(ui, |ui| {
@@ -115,6 +225,56 @@ impl MinesweepRsApp {
                         self.refresh();
                     }
 
+                    // hint btn
+                    let is_watching = self.replay_viewer.is_some();
+                    let is_playing = self.session.is_playing() && !self.session.is_paused() && !is_watching;
+                    let hint_btn = ui.add_enabled(
+                        is_playing,
+                        Button::new(
+                            RichText::new(Self::HINT_BTN_CHAR).text_style(TextStyle::Heading),
+                        )
+                    ).on_hover_text("Show me a safe spot");
+
+                    if hint_btn.clicked() {
+                        self.show_hint();
+                    }
+
+                    // undo and redo btns (a won game is final)
+                    let undo_btn = ui.add_enabled(
+                        !is_watching && self.session.can_undo(),
+                        Button::new(
+                            RichText::new(Self::UNDO_BTN_CHAR).text_style(TextStyle::Heading),
+                        )
+                    ).on_hover_text("Undo");
+
+                    if undo_btn.clicked() {
+                        self.undo();
+                    }
+
+                    let redo_btn = ui.add_enabled(
+                        !is_watching && self.session.can_redo(),
+                        Button::new(
+                            RichText::new(Self::REDO_BTN_CHAR).text_style(TextStyle::Heading),
+                        )
+                    ).on_hover_text("Redo");
+
+                    if redo_btn.clicked() {
+                        self.redo(Self::now_millis(ui));
+                    }
+
+                    // pause btn
+                    let is_paused = self.session.is_paused();
+                    let pause_btn = ui.add_enabled(
+                        self.session.state() == GameState::Running && !is_watching,
+                        Button::new(
+                            RichText::new(if is_paused { Self::PLAY_BTN_CHAR } else { Self::PAUSE_BTN_CHAR }).text_style(TextStyle::Heading),
+                        )
+                    ).on_hover_text(if is_paused { "Resume" } else { "Pause" });
+
+                    if pause_btn.clicked() {
+                        self.toggle_pause();
+                    }
+
                     ui.separator();
 
                     ui.allocate_ui_with_layout(Vec2::new(10.0, 10.0), Layout::left_to_right(egui::Align::Center), |ui| {
@@ -124,7 +284,7 @@ impl MinesweepRsApp {
                         ));
                         ui.add(
                             Label::new(
-                            RichText::new(format!("{}", self.minefield.mines())).monospace().text_style(TextStyle::Heading)
+                            RichText::new(format!("{}", self.session.minefield().mines())).monospace().text_style(TextStyle::Heading)
                         ));
                     });
 
@@ -136,10 +296,10 @@ impl MinesweepRsApp {
                             RichText::new("Flags").text_style(TextStyle::Body)
                         ));
 
-                        let flag_count_color = if self.minefield.mines() as i32 >= self.placed_flags { Self::FLAG_COUNT_OK_COLOR } else { Self::FLAG_COUNT_ERR_COLOR };
+                        let flag_count_color = if self.session.minefield().mines() as i32 >= self.session.placed_flags() { Self::FLAG_COUNT_OK_COLOR } else { Self::FLAG_COUNT_ERR_COLOR };
                         ui.add(
                             Label::new(
-                                RichText::new(format!("{}", self.placed_flags))
+                                RichText::new(format!("{}", self.session.placed_flags()))
                                 .color(flag_count_color)
                                 .monospace()
                                 .text_style(TextStyle::Heading)
@@ -155,7 +315,20 @@ impl MinesweepRsApp {
                         ));
                         ui.add(
                             Label::new(
-                            RichText::new(format!("{}", self.seconds_lapsed)).monospace().text_style(TextStyle::Heading)
+                            RichText::new(clock::format_seconds(self.session.elapsed())).monospace().text_style(TextStyle::Heading)
+                        ));
+                    });
+
+                    ui.separator();
+
+                    ui.allocate_ui_with_layout(Vec2::new(10.0, 10.0), Layout::left_to_right(egui::Align::Center), |ui| {
+                        ui.add(
+                            Label::new(
+                            RichText::new("Hints").text_style(TextStyle::Body)
+                        ));
+                        ui.add(
+                            Label::new(
+                            RichText::new(format!("{}", self.hints_used)).monospace().text_style(TextStyle::Heading)
                         ));
                     });
 
@@ -171,31 +344,156 @@ impl MinesweepRsApp {
                             RichText::new(Self::SETTINGS_BTN_CHAR).text_style(TextStyle::Heading),
                         )
                     ).clicked() {
-                        if let UiToolbarGroup::Settings(_) = self.ui_toolbar_group {
+                        self.toggle_settings();
+                    }
+
+                    // game file button
+                    if ui.add(
+                        Button::new(
+                            RichText::new(Self::GAME_FILE_BTN_CHAR).text_style(TextStyle::Heading)
+                        )
+                    ).on_hover_text("Export or import a game").clicked() {
+                        if let UiToolbarGroup::GameFile = self.ui_toolbar_group {
                             self.ui_toolbar_group = UiToolbarGroup::None;
                         } else {
-                            self.ui_toolbar_group = UiToolbarGroup::Settings(self.game_config);
+                            self.game_file_message.clear();
+                            self.ui_toolbar_group = UiToolbarGroup::GameFile;
                         }
                     }
 
-                    // about button
+                    // statistics button
                     if ui.add(
                         Button::new(
-                            RichText::new(Self::ABOUT_BTN_CHAR).text_style(TextStyle::Heading)
+                            RichText::new(Self::STATS_BTN_CHAR).text_style(TextStyle::Heading)
                         )
-                    ).clicked() {
-                        if let UiToolbarGroup::About = self.ui_toolbar_group {
+                    ).on_hover_text("Statistics").clicked() {
+                        if let UiToolbarGroup::Stats = self.ui_toolbar_group {
+                            self.ui_toolbar_group = UiToolbarGroup::None;
+                        } else {
+                            self.stats_message.clear();
+                            self.ui_toolbar_group = UiToolbarGroup::Stats;
+                        }
+                    }
+
+                    // best times button
+                    if ui.add(
+                        Button::new(
+                            RichText::new(Self::BEST_TIMES_BTN_CHAR).text_style(TextStyle::Heading)
+                        )
+                    ).on_hover_text("Best times").clicked() {
+                        if let UiToolbarGroup::BestTimes = self.ui_toolbar_group {
                             self.ui_toolbar_group = UiToolbarGroup::None;
                         } else {
-                            self.ui_toolbar_group = UiToolbarGroup::About;
+                            self.ui_toolbar_group = UiToolbarGroup::BestTimes;
                         }
                     }
+
+                    // about button
+                    if ui.add(
+                        Button::new(
+                            RichText::new(Self::ABOUT_BTN_CHAR).text_style(TextStyle::Heading)
+                        )
+                    ).clicked() {
+                        self.toggle_about();
+                    }
                 });
             });
             ui.add_space(10.);
         });
     }
 
+    /// Open the Settings window, or close it if it's open
+    fn toggle_settings(&mut self) {
+        if let UiToolbarGroup::Settings(_) = self.ui_toolbar_group {
+            self.ui_toolbar_group = UiToolbarGroup::None;
+        } else {
+            self.ui_toolbar_group = UiToolbarGroup::Settings(self.game_config);
+            self.settings_difficulty = GameDifficulty::from_config(&self.game_config);
+        }
+    }
+
+    /// Open the About window, or close it if it's open
+    fn toggle_about(&mut self) {
+        if let UiToolbarGroup::About = self.ui_toolbar_group {
+            self.ui_toolbar_group = UiToolbarGroup::None;
+        } else {
+            self.ui_toolbar_group = UiToolbarGroup::About;
+        }
+    }
+
+    /// Act on the keys pressed since the previous frame, unless they are typed into a text field
+    fn handle_keys(&mut self, ctx: &Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+
+        // Shortcuts with modifiers are left to the system
+        let pressed_keys: Vec<Key> = ctx.input().events.iter().filter_map(|event| match event {
+            Event::Key { key, pressed: true, modifiers } if !modifiers.ctrl && !modifiers.alt && !modifiers.command => Some(*key),
+            _ => None,
+        }).collect();
+
+        for key in pressed_keys {
+            // A key being bound isn't acted on. Escape cancels the binding.
+            if let Some(action) = self.key_capture.take() {
+                if key != Key::Escape {
+                    self.key_bindings.bind(action, key);
+                }
+
+                continue;
+            }
+
+            if let Some(action) = self.key_bindings.action(key) {
+                self.do_key_action(action, (ctx.input().time * 1000.0) as u64);
+            }
+        }
+    }
+
+    fn do_key_action(&mut self, action: KeyAction, now_millis: u64) {
+        let (width, height) = (self.session.minefield().width(), self.session.minefield().height());
+
+        // The cursor shows up in the middle of the board
+        let (x, y) = self.cursor
+            .filter(|&(x, y)| x < width && y < height)
+            .unwrap_or((width / 2, height / 2));
+
+        let is_playing = self.session.is_playing() && self.replay_viewer.is_none();
+
+        match action {
+            KeyAction::Up => self.move_cursor(x, y.saturating_sub(1)),
+            KeyAction::Down => self.move_cursor(x, (y + 1).min(height - 1)),
+            KeyAction::Left => self.move_cursor(x.saturating_sub(1), y),
+            KeyAction::Right => self.move_cursor((x + 1).min(width - 1), y),
+            KeyAction::Step if is_playing => self.play_spot(x, y, PointerButton::Primary, now_millis),
+            KeyAction::Flag if is_playing => self.play_spot(x, y, PointerButton::Secondary, now_millis),
+            KeyAction::Chord if is_playing => self.play_spot(x, y, PointerButton::Middle, now_millis),
+            KeyAction::Step | KeyAction::Flag | KeyAction::Chord => {},
+            KeyAction::NewGame => self.refresh(),
+            KeyAction::Pause if self.replay_viewer.is_none() => self.toggle_pause(),
+            KeyAction::Pause => {},
+            KeyAction::Settings => self.toggle_settings(),
+            KeyAction::About => self.toggle_about(),
+        }
+    }
+
+    /// Pause the running game, or resume the paused one
+    fn toggle_pause(&mut self) {
+        if !self.session.resume() {
+            self.session.pause();
+        }
+    }
+
+    /// Watch a replay in place of the game, which is paused (if running) so that its clock stops meanwhile
+    fn watch_replay(&mut self, replay: Replay) {
+        self.session.pause();
+        self.replay_viewer = Some(ReplayViewer::new(replay));
+    }
+
+    fn move_cursor(&mut self, x: u16, y: u16) {
+        self.cursor = Some((x, y));
+        self.scroll_to_cursor = true;
+    }
+
     fn render_toolbar_group(&mut self, ctx: &Context, _: &mut Frame) {
         let mut open = true;
 
@@ -219,20 +517,57 @@ impl MinesweepRsApp {
             // Settings window
             UiToolbarGroup::Settings(mut game_config) => {
                 Window::new("Settings").open(&mut open).show(ctx, |ui| {
-                    let currently_selected = GameDifficulty::from_config(&game_config);
+                    let currently_selected = self.settings_difficulty;
                     let mut selected = currently_selected;
-                    
-                    ComboBox::from_label("Game difficulty")
-                        .selected_text(format!("{:?}", selected))
-                        .show_ui(ui, |ui| {
-                            ui.selectable_value(&mut selected, GameDifficulty::Easy, "Easy");
-                            ui.selectable_value(&mut selected, GameDifficulty::Medium, "Medium");
-                            ui.selectable_value(&mut selected, GameDifficulty::Hard, "Hard");
+                    let mut no_guess = game_config.no_guess;
+
+                    ui.horizontal(|ui| {
+                        ComboBox::from_label("Game difficulty")
+                            .selected_text(format!("{:?}", selected))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut selected, GameDifficulty::Easy, "Easy");
+                                ui.selectable_value(&mut selected, GameDifficulty::Medium, "Medium");
+                                ui.selectable_value(&mut selected, GameDifficulty::Hard, "Hard");
+                                ui.selectable_value(&mut selected, GameDifficulty::Custom, "Custom");
+                            }
+                        );
+
+                        ui.checkbox(&mut no_guess, "No guessing")
+                            .on_hover_text("Only generate boards which can be cleared by logic alone");
+                    });
+
+                    if no_guess != game_config.no_guess {
+                        game_config.no_guess = no_guess;
+
+                        // Save the new config into the toolbar window variant (don't apply yet!)
+                        self.ui_toolbar_group = UiToolbarGroup::Settings(game_config);
+                    }
+
+                    // The search for a board without guessing gives up (and falls back to an ordinary board) after
+                    // trying this many boards
+                    if game_config.no_guess {
+                        let mut budget = game_config.no_guess_budget;
+
+                        Grid::new("no_guess_budget").show(ui, |ui| {
+                            ui.label("Boards tried");
+                            ui.add(Slider::new(&mut budget.attempts, 1..=10_000).logarithmic(true));
+                            ui.end_row();
+                        });
+
+                        if budget != game_config.no_guess_budget {
+                            game_config.no_guess_budget = budget;
+
+                            // Save the new config into the toolbar window variant (don't apply yet!)
+                            self.ui_toolbar_group = UiToolbarGroup::Settings(game_config);
                         }
-                    );
+                    }
 
                     if selected != currently_selected {
                         tracing::debug!("\tprev {:?} {:?}", currently_selected, game_config);
+                        self.settings_difficulty = selected;
+
+                        // The difficulty only dictates the board size and mine count, so keep the other settings
+                        let previous_config = game_config;
 
                         match selected {
                             GameDifficulty::Easy => {
@@ -244,18 +579,126 @@ impl MinesweepRsApp {
                             GameDifficulty::Hard => {
                                 game_config = GameDifficulty::HARD;
                             },
+                            GameDifficulty::Custom => {
+                                // Start from the current board, and let the sliders below change it
+                            },
                         }
 
+                        game_config.first_step = previous_config.first_step;
+                        game_config.no_guess = previous_config.no_guess;
+                        game_config.no_guess_budget = previous_config.no_guess_budget;
+
                         // Save the new config into the toolbar window variant (don't apply yet!)
                         self.ui_toolbar_group = UiToolbarGroup::Settings(game_config);
                         tracing::debug!("\tnew: {:?} {:?}", selected, game_config);
                     }
 
+                    // The board size and mine count of a custom difficulty can be changed freely
+                    if self.settings_difficulty == GameDifficulty::Custom {
+                        let mut custom_config = game_config;
+
+                        Grid::new("custom_difficulty").show(ui, |ui| {
+                            ui.label("Width");
+                            ui.add(Slider::new(&mut custom_config.width, GameConfig::MIN_WIDTH..=GameConfig::MAX_WIDTH));
+                            ui.end_row();
+
+                            ui.label("Height");
+                            ui.add(Slider::new(&mut custom_config.height, GameConfig::MIN_HEIGHT..=GameConfig::MAX_HEIGHT));
+                            ui.end_row();
+
+                            ui.label("Mines");
+                            let max_mines = custom_config.max_mines();
+                            ui.add(Slider::new(&mut custom_config.mines, 1..=max_mines));
+                            ui.end_row();
+                        });
+
+                        // Shrinking the board may leave too many mines
+                        let custom_config = custom_config.validated();
+                        if custom_config != game_config {
+                            game_config = custom_config;
+
+                            // Save the new config into the toolbar window variant (don't apply yet!)
+                            self.ui_toolbar_group = UiToolbarGroup::Settings(game_config);
+                        }
+                    }
+
+                    let mut first_step = game_config.first_step;
+
+                    ComboBox::from_label("First step")
+                        .selected_text(format!("{:?}", first_step))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut first_step, FirstStepPolicy::Unprotected, "Unprotected");
+                            ui.selectable_value(&mut first_step, FirstStepPolicy::SafeSpot, "Safe spot");
+                            ui.selectable_value(&mut first_step, FirstStepPolicy::SafeNeighborhood, "Safe neighborhood");
+                        }
+                    );
+
+                    if first_step != game_config.first_step {
+                        game_config.first_step = first_step;
+
+                        // Save the new config into the toolbar window variant (don't apply yet!)
+                        self.ui_toolbar_group = UiToolbarGroup::Settings(game_config);
+                    }
+
+                    ui.separator();
+
+                    // Seed of the current game, which can be copied and typed back in to replay the same layout
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Current seed: {}", self.seed));
+                        if ui.button(Self::COPY_BTN_CHAR).on_hover_text("Copy seed").clicked() {
+                            ui.output().copied_text = self.seed.to_string();
+                        }
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("Seed");
+                        ui.add(TextEdit::singleline(&mut self.seed_input).hint_text("random"));
+                    });
+
+                    let seed_input = self.seed_input.trim();
+                    let seed = if seed_input.is_empty() { Some(rand::random()) } else { seed_input.parse::<u64>().ok() };
+                    if seed.is_none() {
+                        ui.colored_label(Self::LOST_COLOR, "The seed must be a number");
+                    }
+
+                    ui.separator();
+
+                    // Key bindings are applied right away
+                    CollapsingHeader::new("Keyboard").show(ui, |ui| {
+                        Grid::new("key_bindings").striped(true).show(ui, |ui| {
+                            for action in KeyAction::ALL {
+                                ui.label(action.name());
+
+                                let keys: Vec<String> = self.key_bindings.keys(action).iter().map(|key| format!("{:?}", key)).collect();
+                                if self.key_capture == Some(action) {
+                                    ui.colored_label(Self::WON_COLOR, "Press a key (Escape to cancel)");
+                                } else {
+                                    ui.label(RichText::new(keys.join(", ")).monospace());
+                                }
+
+                                if ui.small_button("+").on_hover_text("Add a key").clicked() {
+                                    self.key_capture = Some(action);
+                                }
+
+                                if ui.small_button("✖").on_hover_text("Remove all keys").clicked() {
+                                    self.key_bindings.clear(action);
+                                }
+
+                                ui.end_row();
+                            }
+                        });
+
+                        if ui.button("Reset keys").clicked() {
+                            self.key_bindings = KeyBindings::default();
+                            self.key_capture = None;
+                        }
+                    });
+
                     ui.with_layout(Layout::right_to_left(Align::TOP), |ui| {
-                        if ui.button("Apply").clicked_by(PointerButton::Primary) {
-                            tracing::debug!("\tapply: {:?}", game_config);
+                        if ui.add_enabled(seed.is_some() && game_config.is_valid(), Button::new("Apply")).clicked_by(PointerButton::Primary) {
+                            tracing::debug!("\tapply: {:?} seed {:?}", game_config, seed);
                             self.game_config = game_config;
-                            self.refresh();
+                            self.refresh_seeded(seed.unwrap_or_default());
                         }
 
                         if ui.button("Cancel").clicked_by(PointerButton::Primary) {
@@ -265,6 +708,295 @@ impl MinesweepRsApp {
                 });                
             },
 
+            // Game export/import window
+            UiToolbarGroup::GameFile => {
+                Window::new("Game file").open(&mut open).show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Player");
+                        ui.add(TextEdit::singleline(&mut self.player_name).hint_text("Anonymous"));
+                    });
+
+                    // Native apps use actual files
+                    #[cfg(not(target_arch = "wasm32"))]
+                    {
+                        ui.horizontal(|ui| {
+                            ui.label("File");
+                            ui.add(TextEdit::singleline(&mut self.game_file).hint_text("minesweep-rs.json"));
+                        });
+
+                        let path = std::path::PathBuf::from(self.game_file.trim());
+
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Export")).clicked() {
+                                self.game_file_message = match self.saved_game().write_to(&path) {
+                                    Ok(()) => format!("Game exported to {}", path.display()),
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Import")).clicked() {
+                                self.game_file_message = match SavedGame::read_from(&path) {
+                                    Ok(saved_game) => {
+                                        self.load_game(saved_game);
+                                        format!("Game imported from {}", path.display())
+                                    },
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+                        });
+
+                        // Mine layouts exchanged with other tools, as binary `.mbf` files or RAW text files
+                        let is_mbf = path.extension().and_then(|extension| extension.to_str()) == Some("mbf");
+
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Export board")).clicked() {
+                                let minefield = self.session.minefield();
+                                let data = if is_mbf {
+                                    board_formats::write_mbf(minefield)
+                                } else {
+                                    board_formats::write_raw(minefield).map(String::into_bytes)
+                                };
+
+                                self.game_file_message = match data {
+                                    Ok(data) => match std::fs::write(&path, data) {
+                                        Ok(()) => format!("Board exported to {}", path.display()),
+                                        Err(e) => e.to_string(),
+                                    },
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Import board")).clicked() {
+                                let minefield = match std::fs::read(&path) {
+                                    Ok(data) if is_mbf => board_formats::read_mbf(&data).map_err(|e| e.to_string()),
+                                    Ok(data) => board_formats::read_raw(&String::from_utf8_lossy(&data)).map_err(|e| e.to_string()),
+                                    Err(e) => Err(e.to_string()),
+                                };
+
+                                self.game_file_message = match minefield {
+                                    Ok(minefield) => {
+                                        self.load_minefield(minefield);
+                                        format!("Board imported from {}", path.display())
+                                    },
+                                    Err(e) => e,
+                                };
+                            }
+                        });
+
+                        // Replays exchanged with other tools, as RAWVF files
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Export replay")).clicked() {
+                                self.game_file_message = match self.replay_file().to_rawvf() {
+                                    Ok(text) => match std::fs::write(&path, text) {
+                                        Ok(()) => format!("Replay exported to {}", path.display()),
+                                        Err(e) => e.to_string(),
+                                    },
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Import replay")).clicked() {
+                                let replay_file = match std::fs::read_to_string(&path) {
+                                    Ok(text) => ReplayFile::from_rawvf(&text).map_err(|e| e.to_string()),
+                                    Err(e) => Err(e.to_string()),
+                                };
+
+                                self.game_file_message = match replay_file {
+                                    Ok(replay_file) => {
+                                        let message = format!("Watching the replay of {}", replay_file.player);
+                                        self.watch_replay(replay_file.replay);
+                                        message
+                                    },
+                                    Err(e) => e,
+                                };
+                            }
+                        });
+                    }
+
+                    // The browser has no file system, so games are exchanged through the clipboard
+                    #[cfg(target_arch = "wasm32")]
+                    {
+                        ui.add(TextEdit::multiline(&mut self.game_file).hint_text("Paste a saved game here"));
+
+                        ui.horizontal(|ui| {
+                            if ui.button("Export").clicked() {
+                                ui.output().copied_text = self.saved_game().to_json();
+                                self.game_file_message = "Game copied to the clipboard".to_string();
+                            }
+
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Import")).clicked() {
+                                self.game_file_message = match SavedGame::from_json(&self.game_file) {
+                                    Ok(saved_game) => {
+                                        self.load_game(saved_game);
+                                        "Game imported".to_string()
+                                    },
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+                        });
+
+                        // Replays exchanged with other tools, as RAWVF text
+                        ui.horizontal(|ui| {
+                            if ui.button("Export replay").clicked() {
+                                self.game_file_message = match self.replay_file().to_rawvf() {
+                                    Ok(text) => {
+                                        ui.output().copied_text = text;
+                                        "Replay copied to the clipboard".to_string()
+                                    },
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+
+                            if ui.add_enabled(!self.game_file.trim().is_empty(), Button::new("Import replay")).clicked() {
+                                self.game_file_message = match ReplayFile::from_rawvf(&self.game_file) {
+                                    Ok(replay_file) => {
+                                        let message = format!("Watching the replay of {}", replay_file.player);
+                                        self.watch_replay(replay_file.replay);
+                                        message
+                                    },
+                                    Err(e) => e.to_string(),
+                                };
+                            }
+                        });
+                    }
+
+                    // Boards in plain text notation, e.g. for puzzles and bug reports
+                    ui.separator();
+                    ui.add(TextEdit::multiline(&mut self.board_text).code_editor().hint_text("Paste a board here"));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy board").clicked() {
+                            ui.output().copied_text = self.session.minefield().to_string();
+                            self.game_file_message = "Board copied to the clipboard".to_string();
+                        }
+
+                        if ui.add_enabled(!self.board_text.trim().is_empty(), Button::new("Load board")).clicked() {
+                            self.game_file_message = match self.board_text.parse::<Minefield>() {
+                                Ok(minefield) => {
+                                    self.load_minefield(minefield);
+                                    "Board loaded".to_string()
+                                },
+                                Err(e) => e.to_string(),
+                            };
+                        }
+                    });
+
+                    if !self.game_file_message.is_empty() {
+                        ui.separator();
+                        ui.label(&self.game_file_message);
+                    }
+                });
+            },
+
+            // Best times window
+            UiToolbarGroup::BestTimes => {
+                Window::new("Best times").open(&mut open).show(ctx, |ui| {
+                    if self.high_scores.tables().is_empty() {
+                        ui.label("No games won yet");
+                    }
+
+                    let current_board = ScoreBoard::of_minefield(self.session.minefield());
+
+                    for table in self.high_scores.tables() {
+                        CollapsingHeader::new(table.board.to_string())
+                            .default_open(table.board == current_board)
+                            .show(ui, |ui| {
+                                Grid::new(table.board).striped(true).show(ui, |ui| {
+                                    ui.label("#");
+                                    ui.label("Time");
+                                    ui.label("3BV/s");
+                                    ui.label("Date");
+                                    ui.label("Seed");
+                                    ui.end_row();
+
+                                    for (rank, score) in table.scores.iter().enumerate() {
+                                        ui.label(format!("{}", rank + 1));
+                                        ui.label(RichText::new(clock::format_seconds(score.elapsed())).monospace());
+                                        let bbbv_per_second = score.metrics.and_then(|metrics| metrics.bbbv_per_second(score.elapsed().as_secs_f64()));
+                                        ui.label(RichText::new(bbbv_per_second.map(|value| format!("{:.2}", value)).unwrap_or_default()).monospace());
+                                        ui.label(&score.date);
+                                        ui.label(RichText::new(format!("{}", score.seed)).monospace());
+                                        if score.assisted {
+                                            ui.label(RichText::new("assisted").small().color(Self::READY_COLOR));
+                                        }
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                    }
+                });
+            },
+
+            // Statistics window
+            UiToolbarGroup::Stats => {
+                Window::new("Statistics").open(&mut open).show(ctx, |ui| {
+                    if self.stats.boards().is_empty() {
+                        ui.label("No games played yet");
+                    }
+
+                    let current_board = ScoreBoard::of_minefield(self.session.minefield());
+                    let seconds = |elapsed: Option<Duration>| elapsed.map_or("-".to_string(), clock::format_seconds);
+
+                    for board_stats in self.stats.boards() {
+                        CollapsingHeader::new(board_stats.board.to_string())
+                            .default_open(board_stats.board == current_board)
+                            .show(ui, |ui| {
+                                Grid::new(("stats", board_stats.board)).striped(true).show(ui, |ui| {
+                                    let win_rate = board_stats.win_rate().map_or("-".to_string(), |rate| format!("{:.0}%", rate * 100.0));
+                                    let rows = [
+                                        ("Played", board_stats.played.to_string()),
+                                        ("Won", board_stats.won.to_string()),
+                                        ("Abandoned", board_stats.abandoned.to_string()),
+                                        ("Assisted", board_stats.assisted.to_string()),
+                                        ("Win rate", win_rate),
+                                        ("Current streak", board_stats.current_streak.to_string()),
+                                        ("Longest streak", board_stats.longest_streak.to_string()),
+                                        ("Average win time", seconds(board_stats.average_win_time())),
+                                        ("Median win time", seconds(board_stats.median_win_time())),
+                                    ];
+
+                                    for (name, value) in rows {
+                                        ui.label(name);
+                                        ui.label(RichText::new(value).monospace());
+                                        ui.end_row();
+                                    }
+                                });
+
+                                // Win times histogram, drawn with text bars
+                                let histogram = board_stats.histogram(Self::HISTOGRAM_BUCKETS);
+                                if !histogram.is_empty() {
+                                    ui.separator();
+                                    Grid::new(("histogram", board_stats.board)).show(ui, |ui| {
+                                        for bucket in histogram {
+                                            ui.label(RichText::new(format!("{}-{} s", bucket.from, bucket.to)).monospace());
+                                            ui.label(RichText::new("█".repeat(bucket.count as usize)).color(Self::WON_COLOR));
+                                            ui.label(RichText::new(bucket.count.to_string()).monospace());
+                                            ui.end_row();
+                                        }
+                                    });
+                                }
+                            });
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy as JSON").clicked() {
+                            ui.output().copied_text = self.stats.to_json();
+                            self.stats_message = "Statistics copied to the clipboard".to_string();
+                        }
+
+                        if ui.add_enabled(!self.stats.boards().is_empty(), Button::new("Reset")).clicked() {
+                            self.stats.reset();
+                            self.stats_message = "Statistics reset".to_string();
+                        }
+                    });
+
+                    if !self.stats_message.is_empty() {
+                        ui.label(&self.stats_message);
+                    }
+                });
+            },
+
             UiToolbarGroup::None => {},
         }
 
@@ -278,7 +1010,12 @@ impl MinesweepRsApp {
         // define a TopBottomPanel widget
         TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.vertical_centered(|ui| {
-                match self.game_state {
+                if self.replay_viewer.is_some() {
+                    self.render_replay_controls(ui);
+                    return;
+                }
+
+                match self.session.state() {
                     GameState::Ready => {
                         ui.add(Label::new(
                             RichText::new("Ready")
@@ -287,7 +1024,42 @@ impl MinesweepRsApp {
                                 .text_style(TextStyle::Monospace),
                         ));
                     },
+                    GameState::Running if self.session.is_paused() => {
+                        ui.add(Label::new(
+                            RichText::new(format!("Paused, press {} to resume", Self::PLAY_BTN_CHAR))
+                                .small()
+                                .color(Self::READY_COLOR)
+                                .text_style(TextStyle::Monospace),
+                        ));
+                    },
                     GameState::Running => {
+                        if self.game_config.no_guess && !self.session.minefield().is_no_guess() {
+                            ui.add(Label::new(
+                                RichText::new("This board may require guessing")
+                                    .small()
+                                    .color(Self::READY_COLOR)
+                                    .text_style(TextStyle::Monospace),
+                            ));
+                        }
+
+                        // The hint is only relevant until its spot is stepped on, or flagged
+                        let hint = self.hint.filter(|hint| {
+                            self.session.minefield().spot(hint.x, hint.y).map(|spot| spot.state()) == Some(SpotState::Hidden)
+                        });
+
+                        if let Some(hint) = hint {
+                            if hint.probability > 0.0 {
+                                ui.add(Label::new(
+                                    RichText::new(format!(
+                                        "No safe spot. The hinted spot is a mine with {:.1}% probability",
+                                        hint.probability * 100.0
+                                    ))
+                                        .small()
+                                        .color(Self::READY_COLOR)
+                                        .text_style(TextStyle::Monospace),
+                                ));
+                            }
+                        }
                     },
                     GameState::Stopped(is_won) => {
                         if is_won {
@@ -303,112 +1075,304 @@ impl MinesweepRsApp {
                                     .text_style(TextStyle::Monospace),
                             ));
                         }
+
+                        if let Some(game_result) = self.game_result {
+                            if game_result.is_assisted() {
+                                ui.add(Label::new(
+                                    RichText::new(format!(
+                                "Assisted game: {} hints and {} undos used",
+                                game_result.hints,
+                                game_result.undos
+                            ))
+                                        .small()
+                                        .color(Self::READY_COLOR)
+                                        .text_style(TextStyle::Monospace),
+                                ));
+                            }
+                        }
+
+                        if let Some(summary) = self.game_result.and_then(|game_result| game_result.summary()) {
+                            ui.add(Label::new(
+                                RichText::new(summary)
+                                    .small()
+                                    .color(Self::READY_COLOR)
+                                    .text_style(TextStyle::Monospace),
+                            ));
+                        }
+
+                        if let Some(rank) = self.high_score_rank {
+                            let message = if rank == 0 { "New best time!".to_string() } else { format!("#{} in the best times", rank + 1) };
+                            ui.add(Label::new(
+                                RichText::new(message)
+                                    .small()
+                                    .color(Self::WON_COLOR)
+                                    .text_style(TextStyle::Monospace),
+                            ));
+                        }
+
+                        if ui.button(format!("{} Watch replay", Self::PLAY_BTN_CHAR)).clicked() {
+                            self.watch_replay(self.recorder.replay().clone());
+                        }
                     },
                 }
             })
         });
     }
 
+    /// Render the playback controls of the replay being watched
+    fn render_replay_controls(&mut self, ui: &mut Ui) {
+        let mut close = false;
+
+        if let Some(replay_viewer) = &mut self.replay_viewer {
+            ui.horizontal(|ui| {
+                let play_char = if replay_viewer.is_playing { Self::PAUSE_BTN_CHAR } else { Self::PLAY_BTN_CHAR };
+                if ui.button(play_char).clicked() {
+                    // Play again from the start, once the end was reached
+                    if !replay_viewer.is_playing && replay_viewer.player.is_finished() {
+                        replay_viewer.player.seek(0);
+                    }
+                    replay_viewer.is_playing = !replay_viewer.is_playing;
+                }
+
+                let duration = replay_viewer.player.replay().duration();
+                let mut position = replay_viewer.player.position();
+                if ui.add(Slider::new(&mut position, 0..=duration).show_value(false)).changed() {
+                    replay_viewer.player.seek(position);
+                }
+
+                ui.label(
+                    RichText::new(format!("{:.1} / {:.1}", position as f64 / 1000.0, duration as f64 / 1000.0))
+                        .monospace()
+                );
+
+                ComboBox::from_id_source("replay_speed")
+                    .selected_text(format!("{}x", replay_viewer.speed))
+                    .show_ui(ui, |ui| {
+                        for speed in ReplayViewer::SPEEDS {
+                            ui.selectable_value(&mut replay_viewer.speed, speed, format!("{}x", speed));
+                        }
+                    });
+
+                if ui.button("Close").clicked() {
+                    close = true;
+                }
+            });
+        }
+
+        if close {
+            self.replay_viewer = None;
+        }
+    }
+
     fn render_minefield(&mut self, ctx: &Context, _: &mut Frame) {
         CentralPanel::default().show(ctx, |ui| {
+            // A replay may have been recorded on a board of another size than the game's
+            let (width, height) = match &self.replay_viewer {
+                Some(replay_viewer) => (replay_viewer.player.minefield().width(), replay_viewer.player.minefield().height()),
+                None => (self.session.minefield().width(), self.session.minefield().height()),
+            };
+            let spacing = ui.spacing().item_spacing;
+            let fit_size = Self::spot_size(ui.available_size(), spacing, width, height);
+            let layout = |spot_size| BoardLayout { width, height, spot_size, spacing };
+
+            // Zoom around the pointer, keeping the spot under it in place
+            let zoom_delta = ui.input().zoom_delta();
+            if zoom_delta != 1.0 && ui.rect_contains_pointer(ui.max_rect()) {
+                let old_pitch = layout(self.board_view.spot_size(fit_size)).pitch();
+                self.board_view.zoom = (self.board_view.zoom * zoom_delta).clamp(BoardView::MIN_ZOOM, BoardView::MAX_ZOOM);
+                let new_pitch = layout(self.board_view.spot_size(fit_size)).pitch();
+
+                let anchor = ui.input().pointer.hover_pos().unwrap_or_default() - ui.max_rect().min;
+                let offset = (self.board_view.offset + anchor) * (new_pitch / old_pitch) - anchor;
+                self.board_view.pan_to = Some(offset);
+            }
+
+            // Pan by dragging the board with the pan modifier held
+            if ui.input().modifiers.shift && ui.input().pointer.primary_down() && ui.rect_contains_pointer(ui.max_rect()) {
+                self.board_view.pan_to = Some(self.board_view.offset - ui.input().pointer.delta());
+            }
+
+            let layout = layout(self.board_view.spot_size(fit_size));
+
+            let mut scroll_area = ScrollArea::both().id_source("minefield");
+            if let Some(offset) = self.board_view.pan_to.take() {
+                scroll_area = scroll_area.scroll_offset(offset.max(Vec2::ZERO));
+            }
+
+            let output = scroll_area.show_viewport(ui, |ui, viewport| {
+                // A replay is watched on its own minefield, which can't be played on
+                if let Some(replay_viewer) = &self.replay_viewer {
+                    let minefield = replay_viewer.player.minefield();
+                    Board::new(layout, |x, y| match minefield.spot(x, y) {
+                        Some(spot) => Self::replay_spot_look(spot),
+                        None => SpotLook::button(Self::HIDDEN_SPOT_CHAR, Self::HIDDEN_SPOT_COLOR).enabled(false),
+                    })
+                        .interactive(false)
+                        .show(ui, viewport);
+
+                    return;
+                }
 
-            let size = 30.0;
-            TableBuilder::new(ui)
-                .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
-                .columns(Size::Absolute { initial: size - 1.0, range: (size - 1.0, size - 1.0) }, self.minefield.width() as usize)
-                .body(|mut body| {
-                    for y in 0..self.minefield.height() {
-                        body.row(size + 2.0, |mut row| {
-                            for x in 0..self.minefield.width() {
-                                row.col(|ui| {
-                                    self.render_spot(x, y, size, ui);
-                                });
-                            }
-                        });
-                    }
+                let is_playing = self.session.is_playing() && !self.session.is_paused();
+                let board = Board::new(layout, |x, y| self.spot_look(x, y))
+                    .interactive(is_playing)
+                    .focused(self.cursor)
+                    .show(ui, viewport);
+
+                // Keep the keyboard cursor in view
+                if let Some((x, y)) = self.cursor.filter(|_| self.scroll_to_cursor) {
+                    ui.scroll_to_rect(layout.spot_rect(x, y).translate(board.response.rect.min.to_vec2()), None);
+                    self.scroll_to_cursor = false;
                 }
-            );
-            
+
+                // The hint gives the board away too, so it's hidden along with it
+                let hint = self.hint.filter(|hint| !self.session.is_paused() && board.hovered == Some((hint.x, hint.y)));
+                if let Some(hint) = hint {
+                    board.response.on_hover_text(format!("Mine probability: {:.1}%", hint.probability * 100.0));
+                }
+
+                // Clicks which end a pan don't play
+                if let Some((x, y, button)) = board.clicked.filter(|_| !ui.input().modifiers.shift) {
+                    self.play_spot(x, y, button, Self::now_millis(ui));
+                }
+            });
+
+            self.board_view.offset = output.state.offset;
+
+            // Boards which don't fit get a minimap of the whole board, showing the part in view (unless it's hidden)
+            let is_hidden = self.session.is_paused() && self.replay_viewer.is_none();
+            let is_larger = layout.size().x > output.inner_rect.width() || layout.size().y > output.inner_rect.height();
+            if is_larger && !is_hidden {
+                self.render_minimap(ctx, output.inner_rect, layout);
+            }
         });
     }
 
-    /// Render one spot/tile at the given field coordinates
-    fn render_spot(&mut self, x: u16, y: u16, size: f32, ui: &mut Ui) {
-        let spot = self.minefield.spot(x, y).unwrap();
+    /// Render an overview of the whole board in a corner of the given viewport. Clicking or dragging on it moves the
+    /// view.
+    fn render_minimap(&mut self, ctx: &Context, viewport: egui::Rect, layout: BoardLayout) {
+        let minefield = match &self.replay_viewer {
+            Some(replay_viewer) => replay_viewer.player.minefield(),
+            None => self.session.minefield(),
+        };
 
-        match self.game_state {
-            GameState::Ready | GameState::Running => {
-                match spot.state() {
-                    SpotState::Hidden => {
-                        let hidden_btn = Button::new(
-                            RichText::new(Self::HIDDEN_SPOT_CHAR)
-                            .color(Self::HIDDEN_SPOT_COLOR)
-                            .monospace()
-                            .size(size)
-                        );
-                        let hidden_btn = ui.add_enabled(true, hidden_btn);
+        let scale = Self::MINIMAP_SIZE / layout.size().max_elem();
+        let minimap_size = layout.size() * scale;
+        let position = viewport.right_bottom() - minimap_size - Vec2::splat(Self::MINIMAP_MARGIN);
+
+        // Big boards are sampled, with at most one block per point of the minimap
+        let columns = minefield.width().min(minimap_size.x as u16).max(1);
+        let rows = minefield.height().min(minimap_size.y as u16).max(1);
+        let block = Vec2::new(minimap_size.x / columns as f32, minimap_size.y / rows as f32);
+
+        let mut pan_to = None;
+
+        egui::Area::new("minimap").order(egui::Order::Foreground).fixed_pos(position).show(ctx, |ui| {
+            let (rect, response) = ui.allocate_exact_size(minimap_size, Sense::click_and_drag());
+            let painter = ui.painter();
+            painter.rect_filled(rect.expand(1.0), 0.0, Self::MINIMAP_BACKGROUND_COLOR);
+
+            for row in 0..rows {
+                for column in 0..columns {
+                    let x = (column as u32 * minefield.width() as u32 / columns as u32) as u16;
+                    let y = (row as u32 * minefield.height() as u32 / rows as u32) as u16;
+                    let color = match minefield.spot(x, y).map(|spot| spot.state()) {
+                        Some(SpotState::Hidden) | None => Self::HIDDEN_SPOT_COLOR,
+                        Some(SpotState::Revealed) => continue,
+                        Some(SpotState::Flagged) => Self::FLAG_COLOR_CORRECT,
+                        Some(SpotState::Exploded) => Self::MINE_EPLODED_COLOR,
+                    };
+
+                    let min = rect.min + Vec2::new(column as f32 * block.x, row as f32 * block.y);
+                    painter.rect_filled(egui::Rect::from_min_size(min, block), 0.0, color);
+                }
+            }
 
-                        if hidden_btn.clicked_by(PointerButton::Primary) {
-                            self.check_ready_to_running();
+            let view = egui::Rect::from_min_size(rect.min + self.board_view.offset * scale, viewport.size() * scale);
+            painter.rect_stroke(view.intersect(rect), 0.0, (1.0, Self::MINIMAP_VIEW_COLOR));
 
-                            if self.minefield.step(x, y) == StepResult::Boom {
-                                self.game_over(false);
-                            } else if self.minefield.is_cleared() {
-                                self.game_over(true);
-                            }
-                        }
+            // Center the view on the pointed spot
+            if let Some(pos) = response.interact_pointer_pos().filter(|_| response.clicked() || response.dragged()) {
+                let pointed = ((pos - rect.min) / scale / layout.pitch()).floor();
+                pan_to = Some((pointed + Vec2::splat(0.5)) * layout.pitch() - viewport.size() / 2.0);
+            }
+        });
 
-                        if hidden_btn.clicked_by(PointerButton::Secondary) {
-                            self.check_ready_to_running();
-                            self.placed_flags += self.minefield.toggle_flag(x, y);
+        if pan_to.is_some() {
+            self.board_view.pan_to = pan_to;
+        }
+    }
 
-                            if self.minefield.is_cleared() {
-                                self.game_over(true);
-                            }
-                        }
-                    },
-                    SpotState::Revealed => {
-                        if let SpotKind::Empty(n) = spot.kind() {
-                            let empty_lbl = Label::new(
-                                RichText::new(Self::EMPTY_SPOT_CHARS[n as usize])
-                                .color(Self::EMPTY_SPOT_COLORS[n as usize])
-                                .monospace()
-                                .size(size)
-                            );
+    /// The largest spot size at which a board of the given width and height fits in the available space. Spots are
+    /// drawn `size - 1` wide and `size + 2` high, with the item spacing between them.
+    fn spot_size(available: Vec2, spacing: Vec2, width: u16, height: u16) -> f32 {
+        let fit_x = available.x / width.max(1) as f32 - spacing.x + 1.0;
+        let fit_y = available.y / height.max(1) as f32 - spacing.y - 2.0;
 
-                            let empty_lbl = ui.add_enabled(true, empty_lbl.sense(Sense::click()));
+        fit_x.min(fit_y).floor().clamp(Self::MIN_SPOT_SIZE, Self::MAX_SPOT_SIZE)
+    }
 
-                            if empty_lbl.clicked_by(PointerButton::Middle) {
-                                self.check_ready_to_running();
+    /// Size of the native window which shows a board of the given width and height at the default spot size, within
+    /// reasonable bounds
+    pub fn window_size(width: u16, height: u16) -> Vec2 {
+        let spot = Vec2::new(Self::DEFAULT_SPOT_SIZE + 8.0, Self::DEFAULT_SPOT_SIZE + 5.0);
+        let size = Vec2::new(width as f32 * spot.x, height as f32 * spot.y) + Self::PANELS_SIZE;
 
-                                if self.minefield.try_resolve_step(x, y) == StepResult::Boom {
-                                    self.game_over(false);
-                                } else if self.minefield.is_cleared() {
-                                    self.game_over(true);
-                                }
-                            }
-                        } else {
-                            unreachable!()
-                        }
-                    },
-                    SpotState::Flagged => {
-                        let flag_btn = Button::new(
-                            RichText::new(Self::FLAG_CHAR)
-                            .color(Self::FLAG_COLOR_CORRECT)
-                            .monospace()
-                            .size(size)
-                        );
-                        let flag_btn = ui.add_enabled(true, flag_btn);
+        size.max(Self::MIN_WINDOW_SIZE).min(Self::MAX_WINDOW_SIZE)
+    }
 
-                        if flag_btn.clicked_by(PointerButton::Secondary) {
-                            self.placed_flags += self.minefield.toggle_flag(x, y);
+    /// Resize the native window when the board size changes (e.g. when the difficulty is changed)
+    fn fit_window_to_board(&mut self, frame: &mut Frame) {
+        let board_size = (self.session.minefield().width(), self.session.minefield().height());
 
-                            if self.minefield.is_cleared() {
-                                self.game_over(true);
-                            }
+        if self.window_board_size != Some(board_size) {
+            self.window_board_size = Some(board_size);
+            frame.set_window_size(Self::window_size(board_size.0, board_size.1));
+        }
+    }
+
+    /// How one spot of a replayed minefield looks, as the player saw it
+    fn replay_spot_look(spot: &Spot) -> SpotLook {
+        match (spot.state(), spot.kind()) {
+            (SpotState::Hidden, _) => SpotLook::button(Self::HIDDEN_SPOT_CHAR, Self::HIDDEN_SPOT_COLOR).enabled(false),
+            (SpotState::Flagged, _) => SpotLook::button(Self::FLAG_CHAR, Self::FLAG_COLOR_CORRECT).enabled(false),
+            (SpotState::Exploded, _) => SpotLook::button(Self::MINE_EXPLODED_CHAR, Self::MINE_EPLODED_COLOR).enabled(false),
+            (SpotState::Revealed, SpotKind::Empty(n)) => {
+                SpotLook::label(Self::EMPTY_SPOT_CHARS[n as usize], Self::EMPTY_SPOT_COLORS[n as usize])
+            },
+            (SpotState::Revealed, SpotKind::Mine) => {
+                // A revealed mine becomes `Exploded`
+                unreachable!()
+            },
+        }
+    }
+
+    /// How the spot/tile at the given field coordinates looks
+    fn spot_look(&self, x: u16, y: u16) -> SpotLook {
+        // Nothing can be studied on the board while the clock is paused
+        if self.session.is_paused() {
+            return SpotLook::button(Self::HIDDEN_SPOT_CHAR, Self::HIDDEN_SPOT_COLOR).enabled(false);
+        }
+
+        let spot = self.session.minefield().spot(x, y).unwrap();
+
+        match self.session.state() {
+            GameState::Ready | GameState::Running => {
+                match (spot.state(), spot.kind()) {
+                    (SpotState::Hidden, _) => {
+                        let look = SpotLook::button(Self::HIDDEN_SPOT_CHAR, Self::HIDDEN_SPOT_COLOR);
+
+                        // Highlight the hinted spot
+                        match self.hint {
+                            Some(hint) if (hint.x, hint.y) == (x, y) => look.fill(Self::HINT_COLOR),
+                            _ => look,
                         }
                     },
-                    SpotState::Exploded => {
+                    (SpotState::Revealed, SpotKind::Empty(n)) => {
+                        SpotLook::label(Self::EMPTY_SPOT_CHARS[n as usize], Self::EMPTY_SPOT_COLORS[n as usize])
+                    },
+                    (SpotState::Flagged, _) => SpotLook::button(Self::FLAG_CHAR, Self::FLAG_COLOR_CORRECT),
+                    (SpotState::Revealed, SpotKind::Mine) | (SpotState::Exploded, _) => {
                         // Can't have exploded mine while gamestate is not `Stopped`
                         unreachable!()
                     },
@@ -416,109 +1380,264 @@ impl MinesweepRsApp {
             },
 
             GameState::Stopped(is_won) => {
-                match spot.state() {
-                    SpotState::Hidden => {
-                        match spot.kind() {
-                            SpotKind::Mine => {
-                                let mine_btn = Button::new(
-                                    RichText::new(Self::MINE_CAHR)
-                                    .color(Self::MINE_COLOR)
-                                    .monospace()
-                                    .size(size)
-                                );
-                                let _ = ui.add_enabled(false, mine_btn);
-                            },
-                            SpotKind::Empty(_) => {
-                                let hidden_btn = Button::new(
-                                    RichText::new(Self::HIDDEN_SPOT_CHAR)
-                                    .color(Self::HIDDEN_SPOT_COLOR)
-                                    .monospace()
-                                    .size(size)
-                                );
-                                let _ = ui.add_enabled(false, hidden_btn);
-                            },
-                        }
+                let look = match (spot.state(), spot.kind()) {
+                    (SpotState::Hidden, SpotKind::Mine) => SpotLook::button(Self::MINE_CAHR, Self::MINE_COLOR),
+                    (SpotState::Hidden, SpotKind::Empty(_)) => SpotLook::button(Self::HIDDEN_SPOT_CHAR, Self::HIDDEN_SPOT_COLOR),
+                    (SpotState::Revealed, SpotKind::Empty(n)) => {
+                        return SpotLook::label(Self::EMPTY_SPOT_CHARS[n as usize], Self::EMPTY_SPOT_COLORS[n as usize])
+                            .enabled(is_won);
                     },
-                    SpotState::Revealed => {
-                        match spot.kind() {
-                            SpotKind::Mine => {
-                                // Can't have a revealed spot of mine kind. If a mine is revealed then the spot's
-                                // state becomes `Exploded`, not `Revealed`
-                                unreachable!()
-                            },
-                            SpotKind::Empty(n) => {
-                                let empty_lbl = Label::new(
-                                    RichText::new(Self::EMPTY_SPOT_CHARS[n as usize])
-                                    .color(Self::EMPTY_SPOT_COLORS[n as usize])
-                                    .monospace()
-                                    .size(size)
-                                );
-                                let _ = ui.add_enabled(is_won, empty_lbl);
-                            },
-                        }
-                    },
-                    SpotState::Flagged => {
-                        match spot.kind() {
-                            SpotKind::Mine => {
-                                let flag_btn = Button::new(
-                                    RichText::new(Self::FLAG_CHAR)
-                                    .color(Self::FLAG_COLOR_CORRECT)
-                                    .monospace()
-                                    .size(size)
-                                );
-                                let _ = ui.add_enabled(false, flag_btn);
-                            },
-                            SpotKind::Empty(_) => {
-                                let flag_btn = Button::new(
-                                    RichText::new(Self::FLAG_CHAR)
-                                    .color(Self::FLAG_COLOR_WRONG)
-                                    .monospace()
-                                    .size(size)
-                                );
-                                let _ = ui.add_enabled(false, flag_btn);
-                            },
-                        }
-                    },
-                    SpotState::Exploded => {
-                        match spot.kind() {
-                            SpotKind::Mine => {
-                                let mine_btn = Button::new(
-                                    RichText::new(Self::MINE_EXPLODED_CHAR)
-                                    .color(Self::MINE_EPLODED_COLOR)
-                                    .monospace()
-                                    .size(size)
-                                );
-                                let _ = ui.add_enabled(false, mine_btn);
-                            },
-                            SpotKind::Empty(_) => {
-                                // Only a spot of kind `Mine` can have the state `Exploded`. Anything else is a mistake
-                                unreachable!()
-                            },
-                        }
+                    (SpotState::Flagged, SpotKind::Mine) => SpotLook::button(Self::FLAG_CHAR, Self::FLAG_COLOR_CORRECT),
+                    (SpotState::Flagged, SpotKind::Empty(_)) => SpotLook::button(Self::FLAG_CHAR, Self::FLAG_COLOR_WRONG),
+                    (SpotState::Exploded, SpotKind::Mine) => SpotLook::button(Self::MINE_EXPLODED_CHAR, Self::MINE_EPLODED_COLOR),
+                    (SpotState::Revealed, SpotKind::Mine) | (SpotState::Exploded, SpotKind::Empty(_)) => {
+                        // If a mine is revealed then the spot's state becomes `Exploded`, not `Revealed`, and only a
+                        // spot of kind `Mine` can have the state `Exploded`. Anything else is a mistake
+                        unreachable!()
                     },
-                }
+                };
+
+                look.enabled(false)
             },
         }
     }
 
+    /// Play the spot/tile at the given field coordinates, which was clicked with the given button at the given time
+    fn play_spot(&mut self, x: u16, y: u16, button: PointerButton, now_millis: u64) {
+        let had_pending_mines = self.session.minefield().has_pending_mines();
+        let outcome = match button {
+            PointerButton::Primary => self.session.step(x, y),
+            PointerButton::Secondary => self.session.flag(x, y),
+            PointerButton::Middle => self.session.chord(x, y),
+            _ => None,
+        };
+
+        if let Some(outcome) = outcome {
+            // The recording drops a move on each undo, so it takes exactly the moves which the history takes
+            if outcome.recorded {
+                match button {
+                    PointerButton::Secondary => self.recorder.record_flag(now_millis, x, y, outcome.flags_change),
+                    PointerButton::Middle => self.recorder.record_step(now_millis, ReplayAction::Chord, x, y, outcome.step_result),
+                    _ => self.recorder.record_step(now_millis, ReplayAction::Step, x, y, outcome.step_result),
+                }
+            }
+
+            // The replay starts from the mines placed by the first step, so that watching it doesn't place them again
+            if had_pending_mines {
+                self.recorder.take_mines(self.session.minefield());
+            }
+
+            self.follow_up(outcome);
+        }
+    }
+
+    /// Undo the last move, in the game and in its recording
+    fn undo(&mut self) {
+        if let Some(outcome) = self.session.undo() {
+            self.recorder.undo();
+            self.follow_up(outcome);
+        }
+    }
+
+    /// Redo the last undone move, in the game and in its recording, at the given time
+    fn redo(&mut self, now_millis: u64) {
+        if let Some(outcome) = self.session.redo() {
+            self.recorder.redo(now_millis);
+            self.follow_up(outcome);
+        }
+    }
+
+    /// Keep the app in line with the game after a move: the clock runs with the game, and a game which is over is
+    /// counted in the stats and high scores
+    fn follow_up(&mut self, outcome: MoveOutcome) {
+        if outcome.started() {
+            self.started_at = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        }
+
+        if let Some(is_won) = outcome.ended() {
+            self.game_over(is_won);
+        } else if matches!(outcome.previous_state, GameState::Stopped(_)) && self.session.is_playing() {
+            // Back in the game, after undoing the move which ended it
+            self.game_result = None;
+        }
+    }
+
     fn game_over(&mut self, is_won: bool) {
-        self.game_state = GameState::Stopped(is_won);
-        self.timer.stop();
+        self.hint = None;
+        self.game_result = Some(self.result(is_won));
+
+        // A game may be lost more than once, by undoing the losing move, but only counts once
+        if !self.is_stats_recorded && !self.is_imported_board {
+            let assisted = self.result(is_won).is_assisted();
+            self.stats.record(ScoreBoard::of_minefield(self.session.minefield()), is_won, self.session.elapsed(), assisted);
+            self.is_stats_recorded = true;
+        }
+
+        if is_won {
+            self.record_high_score();
+        }
+    }
+
+    /// Count the running game as abandoned in the stats, before it is replaced by another one
+    fn abandon_game(&mut self) {
+        if self.session.state() == GameState::Running && !self.is_stats_recorded && !self.is_imported_board {
+            self.stats.record_abandoned(ScoreBoard::of_minefield(self.session.minefield()));
+            self.is_stats_recorded = true;
+        }
+    }
+
+    /// Add the won game to the best times of its board
+    fn record_high_score(&mut self) {
+        // An imported board may have been partially cleared before it was loaded
+        if self.is_imported_board {
+            return;
+        }
+
+        let score = HighScore {
+            seconds: self.session.seconds_lapsed(),
+            millis: Some(self.session.elapsed().as_millis() as u64),
+            date: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            seed: self.seed,
+            assisted: self.result(true).is_assisted(),
+            metrics: self.result(true).metrics,
+        };
+
+        self.high_score_rank = self.high_scores.record(ScoreBoard::of_minefield(self.session.minefield()), score);
+    }
+
+    /// The result of the game, if it were to end now
+    fn result(&self, is_won: bool) -> GameResult {
+        GameResult {
+            is_won,
+            elapsed: self.session.elapsed(),
+            hints: self.hints_used,
+            undos: self.session.undos_used(),
+            metrics: self.session.metrics(),
+        }
+    }
+
+    /// Snapshot of the current game (the history of moves is not saved)
+    fn saved_game(&self) -> SavedGame {
+        SavedGame {
+            version: save::FORMAT_VERSION,
+            game_config: self.game_config,
+            seed: self.seed,
+            minefield: self.session.minefield().clone(),
+            game_state: self.session.state(),
+            seconds_lapsed: self.session.seconds_lapsed(),
+            millis_lapsed: Some(self.session.elapsed().as_millis() as u64),
+            placed_flags: self.session.placed_flags(),
+            hints_used: self.hints_used,
+            undos_used: self.session.undos_used(),
+            clicks: self.session.clicks(),
+        }
+    }
+
+    /// Continue a saved game
+    fn load_game(&mut self, saved_game: SavedGame) {
+        self.abandon_game();
+
+        self.game_config = saved_game.game_config.validated();
+        self.seed = saved_game.seed;
+        self.session = GameSession::from_saved_game(&saved_game);
+        self.hints_used = saved_game.hints_used;
+        self.hint = None;
+        self.game_result = None;
+        self.replay_viewer = None;
+        self.high_score_rank = None;
+        self.is_imported_board = false;
+        self.start_recording();
+
+        // Finished games were already counted when they were played
+        self.is_stats_recorded = !self.session.is_playing();
+
+        if let GameState::Stopped(is_won) = self.session.state() {
+            self.game_result = Some(self.result(is_won));
+        }
     }
 
-    fn check_ready_to_running(&mut self) {
-        if self.game_state == GameState::Ready {
-            self.game_state = GameState::Running;
-            self.timer.start();
+    /// Start playing on a given minefield, picking up from its play state
+    fn load_minefield(&mut self, minefield: Minefield) {
+        self.abandon_game();
+
+        self.session = GameSession::new(minefield);
+        self.hint = None;
+        self.hints_used = 0;
+        self.game_result = None;
+        self.replay_viewer = None;
+        self.high_score_rank = None;
+        self.is_imported_board = true;
+        self.start_recording();
+
+        // A loaded board may already be lost or won
+        if let GameState::Stopped(is_won) = self.session.state() {
+            self.game_over(is_won);
         }
     }
 
+    /// Point out a hidden spot which is certainly safe or, if there is no such spot, the one least likely to be a mine
+    fn show_hint(&mut self) {
+        let hint = if self.session.minefield().has_pending_mines() {
+            // Mines are placed on the first step, and never on the stepped spot
+            Some(Hint { x: self.session.minefield().width() / 2, y: self.session.minefield().height() / 2, probability: 0.0 })
+        } else if let Some(&(x, y)) = solver::solve(self.session.minefield()).safe.iter().next() {
+            Some(Hint { x, y, probability: 0.0 })
+        } else {
+            solver::probabilities(self.session.minefield(), self.seed).safest().map(|((x, y), probability)| Hint { x, y, probability })
+        };
+
+        if hint.is_some() {
+            self.hint = hint;
+            self.hints_used += 1;
+        }
+    }
+
+    /// The recording of the current game, as stored in replay files
+    fn replay_file(&self) -> ReplayFile {
+        ReplayFile {
+            player: self.player_name.clone(),
+            timestamp: self.started_at.clone(),
+            replay: self.recorder.replay().clone(),
+        }
+    }
+
+    /// Start recording the moves made on the current minefield
+    fn start_recording(&mut self) {
+        self.recorder = Recorder::new(self.seed, self.session.minefield().clone());
+    }
+
+    /// Time of the current frame, in milliseconds, for recording moves
+    fn now_millis(ui: &Ui) -> u64 {
+        (ui.input().time * 1000.0) as u64
+    }
+
+    /// Start a new game, with a random seed
     fn refresh(&mut self) {
-        let minefield = Minefield::new(self.game_config.width, self.game_config.height).with_mines(self.game_config.mines);
+        self.refresh_seeded(rand::random());
+    }
+
+    /// Start a new game, with mines placed according to the given seed
+    fn refresh_seeded(&mut self, seed: u64) {
+        self.abandon_game();
+
+        let session = GameSession::new(self.game_config.minefield(seed));
+        let recorder = Recorder::new(seed, session.minefield().clone());
         let game_config = self.game_config;
+        let player_name = std::mem::take(&mut self.player_name);
+        let high_scores = std::mem::take(&mut self.high_scores);
+        let stats = std::mem::take(&mut self.stats);
+        let window_board_size = self.window_board_size;
+        let key_bindings = std::mem::take(&mut self.key_bindings);
         *self = Self {
-            minefield,
+            session,
+            seed,
             game_config,
+            recorder,
+            player_name,
+            high_scores,
+            stats,
+            window_board_size,
+            key_bindings,
             ..Default::default()
         };
     }
@@ -528,159 +1647,135 @@ impl MinesweepRsApp {
 impl Default for MinesweepRsApp {
     fn default() -> Self {
         let game_config = GameConfig::default();
+        let seed = rand::random();
+        let session = GameSession::new(game_config.minefield(seed));
         Self {
-            minefield: Minefield::new(game_config.width, game_config.height).with_mines(game_config.mines),
-            placed_flags: 0,
-            seconds_lapsed: 0,
-            timer: AppTimer::default(),
-            game_state: GameState::default(),
+            recorder: Recorder::new(seed, session.minefield().clone()),
+            session,
+            seed,
+            seed_input: String::new(),
+            game_result: None,
             game_config,
             ui_toolbar_group: UiToolbarGroup::default(),
+            settings_difficulty: GameDifficulty::from_config(&game_config),
+            hint: None,
+            hints_used: 0,
+            game_file: String::new(),
+            game_file_message: String::new(),
+            board_text: String::new(),
+            replay_viewer: None,
+            player_name: String::new(),
+            started_at: String::new(),
+            high_scores: HighScores::default(),
+            high_score_rank: None,
+            is_imported_board: false,
+            stats: Stats::default(),
+            is_stats_recorded: false,
+            stats_message: String::new(),
+            board_view: BoardView::default(),
+            window_board_size: None,
+            key_bindings: KeyBindings::default(),
+            key_capture: None,
+            cursor: None,
+            scroll_to_cursor: false,
         }
     }
 }
 
-/// Current state of the game
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
-enum GameState {
-    /// Game is ready to start running
-    Ready,
+/// Zoom and scroll position of the minefield view
+struct BoardView {
+    /// Scale of the spots, relative to the size at which the whole board fits
+    zoom: f32,
 
-    /// Game is running
-    Running,
+    /// Scroll position of the board, as of the previous frame
+    offset: Vec2,
 
-    /// Game is stopped, and was either won (`true`), or lost (`false`)
-    Stopped(bool)
+    /// Scroll position to move to, on the next frame
+    pan_to: Option<Vec2>,
 }
 
-impl Default for GameState {
-    fn default() -> Self {
-        Self::Ready
-    }
-}
+impl BoardView {
+    const MIN_ZOOM: f32 = 0.25;
+    const MAX_ZOOM: f32 = 8.0;
 
-enum UiToolbarGroup {
-    None,
-    About,
-    Settings(GameConfig),
-}
+    /// Range of the zoomed spot size
+    const MIN_SPOT_SIZE: f32 = 6.0;
+    const MAX_SPOT_SIZE: f32 = 96.0;
 
-impl Default for UiToolbarGroup {
-    fn default() -> Self {
-        Self::None
+    /// Size of the spots at the current zoom, given the size at which the whole board fits
+    fn spot_size(&self, fit_size: f32) -> f32 {
+        (fit_size * self.zoom).floor().clamp(Self::MIN_SPOT_SIZE, Self::MAX_SPOT_SIZE)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub struct GameConfig {
-    pub width: u16,
-    pub height: u16,
-    pub mines: u16,
-}
-
-impl Default for GameConfig {
+impl Default for BoardView {
     fn default() -> Self {
-        Self { width: 10, height: 10, mines: 10 }
+        Self { zoom: 1.0, offset: Vec2::ZERO, pan_to: None }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub enum GameDifficulty {
-    Easy,
-    Medium,
-    Hard,
-}
+/// Playback state of the replay being watched
+struct ReplayViewer {
+    player: Player,
+    speed: f64,
+    is_playing: bool,
 
-impl GameDifficulty {
-    pub const EASY: GameConfig = GameConfig { width: 10, height: 10, mines: 10 };
-    pub const MEDIUM: GameConfig = GameConfig { width: 16, height: 16, mines: 40 };
-    pub const HARD: GameConfig = GameConfig { width: 30, height: 16, mines: 99 };
-
-    pub fn from_config(config: &GameConfig) -> Self {
-        if *config == Self::EASY {
-            Self::Easy
-        } else if *config == Self::MEDIUM {
-            Self::Medium
-        } else if *config == Self::HARD {
-            Self::Hard
-        } else {
-            unreachable!()
-        }
-    }
+    /// Input time (in seconds) of the previous frame, while playing
+    last_time: Option<f64>,
 }
 
-/// Native app timer
-#[cfg(not(target_arch = "wasm32"))]
-#[derive(Default)]
-struct AppTimer {
-    timer: Option<Timer>,
-    guard: Option<Guard>,
-    rx: Option<Receiver<()>>
-}
+impl ReplayViewer {
+    const SPEEDS: [f64; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
 
-/// WASM app timer
-#[cfg(target_arch = "wasm32")]
-#[derive(Default)]
-struct AppTimer {
-    timer: Option<Interval>,
-    rx: Option<Receiver<()>>
-}
+    fn new(replay: Replay) -> Self {
+        Self { player: Player::new(replay), speed: 1.0, is_playing: true, last_time: None }
+    }
 
-impl AppTimer {
-    pub fn stop(&mut self) {
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            self.guard = None;
-            self.timer = None;
-            self.rx = None;
-        }
+    /// Move the playback forward to the given input time (in seconds)
+    fn tick(&mut self, time: f64) {
+        if self.is_playing {
+            if let Some(last_time) = self.last_time {
+                self.player.advance((time - last_time) * 1000.0 * self.speed);
+            } else {
+                self.player.advance(0.0);
+            }
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            if let Some(prev_interval) = self.timer.take() {
-                prev_interval.cancel();
-            }            
+            if self.player.is_finished() {
+                self.is_playing = false;
+            }
+
+            self.last_time = Some(time);
+        } else {
+            self.last_time = None;
         }
     }
+}
 
-    pub fn start(&mut self) {
-        let (tx, rx) = channel();
-
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            use chrono::Duration;
+/// A hidden spot suggested to the player
+#[derive(Copy, Clone, PartialEq, Debug)]
+struct Hint {
+    x: u16,
+    y: u16,
 
-            let timer = Timer::new();
-            let guard = timer.schedule_repeating(Duration::seconds(1), move || {
-                    tx.send(()).unwrap();
-            });
-    
-            self.timer = Some(timer);
-            self.guard = Some(guard);
-            self.rx = Some(rx);
-        }
+    /// Probability of the spot being a mine (`0.0` if it is certainly safe)
+    probability: f64,
+}
 
-        #[cfg(target_arch = "wasm32")]
-        {
-            let interval = Interval::new(1000, move || {
-                tx.send(()).unwrap();
-            }); 
-            
-            self.timer = Some(interval);
-            self.rx = Some(rx);
-        }        
-    }
+enum UiToolbarGroup {
+    None,
+    About,
+    Settings(GameConfig),
+    GameFile,
+    BestTimes,
+    Stats,
+}
 
-    pub fn poll(&self) -> Option<()> {
-        if let Some(rx) = &self.rx {
-            rx.try_iter().next()
-        } else {
-            None
-        }
+impl Default for UiToolbarGroup {
+    fn default() -> Self {
+        Self::None
     }
 }
 
-
 #[cfg(target_arch = "wasm32")]
 use eframe::wasm_bindgen::{self, prelude::*};
 
@@ -700,4 +1795,98 @@ pub fn main_web(canvas_id: &str) {
 
     eframe::start_web(canvas_id, options, Box::new(|cc| Box::new(MinesweepRsApp::default().with_context(cc))))
         .expect("Failed to launch minesweep-rs");
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responsive_layout() {
+        let spacing = Vec2::new(8.0, 3.0);
+
+        // Spots grow and shrink with the available space, within limits
+        assert_eq!(MinesweepRsApp::spot_size(Vec2::new(370.0, 350.0), spacing, 10, 10), 30.0);
+        assert_eq!(MinesweepRsApp::spot_size(Vec2::new(370.0, 200.0), spacing, 10, 10), 15.0);
+        assert_eq!(MinesweepRsApp::spot_size(Vec2::new(2000.0, 2000.0), spacing, 10, 10), MinesweepRsApp::MAX_SPOT_SIZE);
+        assert_eq!(MinesweepRsApp::spot_size(Vec2::new(800.0, 600.0), spacing, 200, 200), MinesweepRsApp::MIN_SPOT_SIZE);
+
+        // The window follows the board size, within limits
+        let easy = MinesweepRsApp::window_size(GameDifficulty::EASY.width, GameDifficulty::EASY.height);
+        let hard = MinesweepRsApp::window_size(GameDifficulty::HARD.width, GameDifficulty::HARD.height);
+        assert!(hard.x > easy.x && hard.y > easy.y);
+        assert_eq!(MinesweepRsApp::window_size(3, 1), MinesweepRsApp::MIN_WINDOW_SIZE);
+        assert_eq!(MinesweepRsApp::window_size(200, 200), MinesweepRsApp::MAX_WINDOW_SIZE);
+    }
+
+    #[test]
+    fn zoom() {
+        let mut board_view = BoardView::default();
+        assert_eq!(board_view.spot_size(30.0), 30.0);
+        board_view.zoom = 2.5;
+        assert_eq!(board_view.spot_size(30.0), 75.0);
+        board_view.zoom = BoardView::MAX_ZOOM;
+        assert_eq!(board_view.spot_size(30.0), BoardView::MAX_SPOT_SIZE);
+        board_view.zoom = BoardView::MIN_ZOOM;
+        assert_eq!(board_view.spot_size(14.0), BoardView::MIN_SPOT_SIZE);
+    }
+
+    #[test]
+    fn replay_starts_from_placed_mines() {
+        let mut app = MinesweepRsApp {
+            session: GameSession::new(Minefield::new(9, 9).with_mines_deferred(10, 42, FirstStepPolicy::SafeNeighborhood)),
+            ..Default::default()
+        };
+        app.start_recording();
+        assert!(app.recorder.replay().start.has_pending_mines());
+
+        app.play_spot(4, 4, PointerButton::Primary, 1_000);
+        let replay = app.recorder.replay();
+        assert!(!replay.start.has_pending_mines());
+        assert_eq!(replay.minefield_at(replay.duration()).to_string(), app.session.minefield().to_string());
+    }
+
+    #[test]
+    fn watching_a_replay_pauses_the_game() {
+        let mut app = MinesweepRsApp {
+            session: GameSession::new(Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)])),
+            ..Default::default()
+        };
+        app.play_spot(1, 2, PointerButton::Primary, 1_000);
+        assert_eq!(app.session.state(), GameState::Running);
+
+        app.watch_replay(Replay::new(0, Minefield::new(9, 9).with_mines_seeded(10, 0)));
+        assert!(app.replay_viewer.is_some());
+        assert!(app.session.is_paused());
+    }
+
+    #[test]
+    fn replay_follows_undo() {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let mut app = MinesweepRsApp {
+            session: GameSession::new(Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)])),
+            ..Default::default()
+        };
+        app.start_recording();
+
+        app.play_spot(0, 0, PointerButton::Primary, 1_000);
+        app.play_spot(2, 0, PointerButton::Secondary, 1_500);
+        app.play_spot(1, 0, PointerButton::Middle, 2_000);
+        assert_eq!(app.session.minefield().spot(2, 1).unwrap().state(), SpotState::Revealed);
+
+        // Chording again around the satisfied 1, which has no hidden neighbors left, changes nothing, so undoing after
+        // it takes back the first chord, both in the game and in its recording
+        assert_eq!(app.session.chord(1, 0).map(|outcome| outcome.recorded), Some(false));
+        app.play_spot(1, 0, PointerButton::Middle, 2_500);
+        app.undo();
+
+        let replay = app.recorder.replay();
+        assert_eq!(replay.events.len(), 2);
+        assert_eq!(replay.minefield_at(replay.duration()).to_string(), app.session.minefield().to_string());
+        assert_eq!(app.session.minefield().spot(2, 1).unwrap().state(), SpotState::Hidden);
+    }
+}
@@ -0,0 +1,294 @@
+//! Terminal frontend, played with the cursor keys or the mouse.
+//!
+//! Run with `cargo run --release --no-default-features --features tui --bin minesweep-tui -- [easy|medium|hard]`
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind},
+    queue,
+    style::{Attribute, Color, Print, PrintStyledContent, Stylize, StyledContent},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use minesweep_rs::{
+    clock,
+    game::{GameConfig, GameDifficulty, GameState},
+    minefield::{SpotKind, SpotState},
+    session::GameSession,
+};
+use std::{
+    env,
+    io::{self, Write},
+    process,
+    time::Duration,
+};
+
+/// Rows above the board, taken by the status line
+const BOARD_TOP: u16 = 2;
+
+/// Columns taken by a spot
+const SPOT_WIDTH: u16 = 2;
+
+/// How often the clock is redrawn while waiting for input
+const REDRAW: Duration = Duration::from_millis(50);
+
+const HIDDEN_SPOT_CHAR: char = '.';
+const FLAG_CHAR: char = 'F';
+const WRONG_FLAG_CHAR: char = 'X';
+const MINE_CHAR: char = '*';
+
+/// Classic colors of the number of neighboring mines
+const EMPTY_SPOT_COLORS: [Color; 9] = [
+    Color::Reset, Color::Blue, Color::Green, Color::Red, Color::DarkBlue,
+    Color::DarkRed, Color::Cyan, Color::Magenta, Color::Grey,
+];
+
+const HELP: &str = "arrows/hjkl move · space step · f flag · c chord · mouse left/right/middle · p pause · n new · 1/2/3 difficulty · q quit";
+
+/// What can be done to a spot
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Action {
+    Step,
+    Flag,
+    Chord,
+}
+
+struct TuiApp {
+    config: GameConfig,
+    session: GameSession,
+
+    /// Spot under the keyboard cursor
+    cursor: (u16, u16),
+}
+
+impl TuiApp {
+    fn new(config: GameConfig) -> Self {
+        Self {
+            config,
+            session: GameSession::new(config.minefield(rand::random())),
+            cursor: (config.width / 2, config.height / 2),
+        }
+    }
+
+    fn restart(&mut self, config: GameConfig) {
+        *self = Self::new(config);
+    }
+
+    fn move_cursor(&mut self, dx: i32, dy: i32) {
+        let (x, y) = self.cursor;
+        self.cursor = (
+            (x as i32 + dx).clamp(0, self.session.minefield().width() as i32 - 1) as u16,
+            (y as i32 + dy).clamp(0, self.session.minefield().height() as i32 - 1) as u16,
+        );
+    }
+
+    /// Play the spot at the given coordinates
+    fn play_spot(&mut self, x: u16, y: u16, action: Action) {
+        match action {
+            Action::Step => self.session.step(x, y),
+            Action::Flag => self.session.flag(x, y),
+            Action::Chord => self.session.chord(x, y),
+        };
+    }
+
+    /// Pause the running game, or resume the paused one
+    fn toggle_pause(&mut self) {
+        if !self.session.resume() {
+            self.session.pause();
+        }
+    }
+
+    /// Handle a key press, and tell whether the app should quit
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+
+            KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => self.move_cursor(0, -1),
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => self.move_cursor(0, 1),
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('a') => self.move_cursor(-1, 0),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('d') => self.move_cursor(1, 0),
+
+            KeyCode::Char(' ') | KeyCode::Enter => self.play_spot(self.cursor.0, self.cursor.1, Action::Step),
+            KeyCode::Char('f') => self.play_spot(self.cursor.0, self.cursor.1, Action::Flag),
+            KeyCode::Char('c') => self.play_spot(self.cursor.0, self.cursor.1, Action::Chord),
+
+            KeyCode::Char('p') => self.toggle_pause(),
+            KeyCode::Char('n') => self.restart(self.config),
+            KeyCode::Char('1') => self.restart(GameDifficulty::EASY),
+            KeyCode::Char('2') => self.restart(GameDifficulty::MEDIUM),
+            KeyCode::Char('3') => self.restart(GameDifficulty::HARD),
+            _ => {},
+        }
+
+        false
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        let action = match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => Action::Step,
+            MouseEventKind::Down(MouseButton::Right) => Action::Flag,
+            MouseEventKind::Down(MouseButton::Middle) => Action::Chord,
+            _ => return,
+        };
+
+        if let Some((x, y)) = Self::spot_at(mouse.column, mouse.row) {
+            if x < self.session.minefield().width() && y < self.session.minefield().height() {
+                self.cursor = (x, y);
+                self.play_spot(x, y, action);
+            }
+        }
+    }
+
+    /// The spot drawn at the given terminal cell, if it is on the board
+    fn spot_at(column: u16, row: u16) -> Option<(u16, u16)> {
+        (row >= BOARD_TOP).then_some((column / SPOT_WIDTH, row - BOARD_TOP))
+    }
+
+    fn spot_look(&self, x: u16, y: u16) -> StyledContent<char> {
+        // Nothing can be studied on the board while the clock is paused
+        if self.session.is_paused() {
+            return HIDDEN_SPOT_CHAR.dark_grey();
+        }
+
+        let spot = self.session.minefield().spot(x, y).unwrap();
+        let is_stopped = !self.session.is_playing();
+
+        match (spot.state(), spot.kind()) {
+            (SpotState::Hidden, SpotKind::Mine) if is_stopped => MINE_CHAR.red(),
+            (SpotState::Hidden, _) => HIDDEN_SPOT_CHAR.dark_grey(),
+            (SpotState::Revealed, SpotKind::Empty(0)) => ' '.reset(),
+            (SpotState::Revealed, SpotKind::Empty(n)) => {
+                char::from_digit(n as u32, 10).unwrap().with(EMPTY_SPOT_COLORS[n as usize]).attribute(Attribute::Bold)
+            },
+            (SpotState::Flagged, SpotKind::Empty(_)) if is_stopped => WRONG_FLAG_CHAR.red().attribute(Attribute::Bold),
+            (SpotState::Flagged, _) => FLAG_CHAR.yellow().attribute(Attribute::Bold),
+            (SpotState::Exploded, _) => MINE_CHAR.white().on_red().attribute(Attribute::Bold),
+            (SpotState::Revealed, SpotKind::Mine) => {
+                // A revealed mine has the state `Exploded`
+                unreachable!()
+            },
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let difficulty = match GameDifficulty::from_config(&self.config) {
+            GameDifficulty::Easy => "Easy",
+            GameDifficulty::Medium => "Medium",
+            GameDifficulty::Hard => "Hard",
+            GameDifficulty::Custom => "Custom",
+        };
+        let minefield = self.session.minefield();
+        let flags = format!("{}/{}", self.session.placed_flags(), minefield.mines());
+        let flags = if self.session.placed_flags() > minefield.mines() as i32 { flags.red() } else { flags.reset() };
+        let status = match self.session.state() {
+            GameState::Ready => "Ready".dark_grey(),
+            GameState::Running if self.session.is_paused() => "Paused".yellow(),
+            GameState::Running => "Running".reset(),
+            GameState::Stopped(true) => "You won!".green(),
+            GameState::Stopped(false) => "Boom!".red(),
+        };
+
+        queue!(
+            out,
+            MoveTo(0, 0),
+            Print(format!("{}  Flags ", difficulty)),
+            PrintStyledContent(flags),
+            Print(format!("  Time {}  ", clock::format_seconds(self.session.elapsed()))),
+            PrintStyledContent(status),
+            Clear(ClearType::UntilNewLine),
+        )?;
+
+        for y in 0..minefield.height() {
+            queue!(out, MoveTo(0, BOARD_TOP + y))?;
+            for x in 0..minefield.width() {
+                let look = self.spot_look(x, y);
+                let look = if (x, y) == self.cursor { look.reverse() } else { look };
+                queue!(out, PrintStyledContent(look), Print(' '))?;
+            }
+            queue!(out, Clear(ClearType::UntilNewLine))?;
+        }
+
+        queue!(
+            out,
+            MoveTo(0, BOARD_TOP + minefield.height() + 1),
+            PrintStyledContent(HELP.dark_grey()),
+            Clear(ClearType::FromCursorDown),
+        )?;
+
+        out.flush()
+    }
+
+    fn run(&mut self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, Clear(ClearType::All))?;
+
+        loop {
+            self.draw(out)?;
+
+            if !event::poll(REDRAW)? {
+                continue;
+            }
+
+            let quit = match event::read()? {
+                // Some platforms also report key releases
+                Event::Key(key) if key.kind != KeyEventKind::Release => self.handle_key(key),
+                Event::Mouse(mouse) => {
+                    self.handle_mouse(mouse);
+                    false
+                },
+                // The game waits while the player is away
+                Event::FocusLost => {
+                    self.session.pause();
+                    false
+                },
+                Event::Resize(_, _) => {
+                    queue!(out, Clear(ClearType::All))?;
+                    false
+                },
+                _ => false,
+            };
+
+            if quit {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Puts the terminal in raw mode for the lifetime of the game, and restores it even if the game panics
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter(out: &mut impl Write) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        queue!(out, EnterAlternateScreen, EnableMouseCapture, EnableFocusChange, Hide)?;
+        out.flush()?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let mut out = io::stdout();
+        let _ = queue!(out, Show, DisableFocusChange, DisableMouseCapture, LeaveAlternateScreen);
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn main() -> io::Result<()> {
+    let config = match env::args().nth(1).as_deref() {
+        None | Some("easy") => GameDifficulty::EASY,
+        Some("medium") => GameDifficulty::MEDIUM,
+        Some("hard") => GameDifficulty::HARD,
+        Some(_) => {
+            eprintln!("usage: minesweep-tui [easy|medium|hard]");
+            process::exit(2);
+        },
+    };
+
+    let mut out = io::stdout();
+    let _raw_terminal = RawTerminal::enter(&mut out)?;
+
+    TuiApp::new(config).run(&mut out)
+}
@@ -0,0 +1,223 @@
+use eframe::{
+    egui::{Ui, Response, Sense, PointerButton, Align2, FontId},
+    epaint::{Color32, Pos2, Rect, Stroke, Vec2, color::tint_color_towards},
+};
+use std::ops::Range;
+
+/// How a spot is drawn
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SpotLook {
+    pub text: &'static str,
+    pub color: Color32,
+
+    /// Drawn as a button, rather than as a label
+    pub raised: bool,
+
+    /// Background of the button, instead of the default one
+    pub fill: Option<Color32>,
+
+    /// Disabled spots are faded out, and aren't highlighted when hovered
+    pub enabled: bool,
+}
+
+impl SpotLook {
+    /// A spot drawn as a button
+    pub fn button(text: &'static str, color: Color32) -> Self {
+        Self { text, color, raised: true, fill: None, enabled: true }
+    }
+
+    /// A spot drawn as a label
+    pub fn label(text: &'static str, color: Color32) -> Self {
+        Self { text, color, raised: false, fill: None, enabled: true }
+    }
+
+    pub fn fill(self, fill: Color32) -> Self {
+        Self { fill: Some(fill), ..self }
+    }
+
+    pub fn enabled(self, enabled: bool) -> Self {
+        Self { enabled, ..self }
+    }
+}
+
+/// Position and size of the spots of a board. Spots are `spot_size - 1` wide and `spot_size + 2` high, with the given
+/// spacing between them.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct BoardLayout {
+    pub width: u16,
+    pub height: u16,
+    pub spot_size: f32,
+    pub spacing: Vec2,
+}
+
+impl BoardLayout {
+    /// Distance between the top left corners of neighboring spots
+    pub fn pitch(&self) -> Vec2 {
+        Vec2::new(self.spot_size - 1.0 + self.spacing.x, self.spot_size + 2.0 + self.spacing.y)
+    }
+
+    /// Size of the whole board
+    pub fn size(&self) -> Vec2 {
+        Vec2::new(self.width as f32, self.height as f32) * self.pitch()
+    }
+
+    /// Area of a spot, relative to the top left corner of the board
+    pub fn spot_rect(&self, x: u16, y: u16) -> Rect {
+        let min = (Vec2::new(x as f32, y as f32) * self.pitch()).to_pos2();
+
+        Rect::from_min_size(min, Vec2::new(self.spot_size - 1.0, self.spot_size + 2.0))
+    }
+
+    /// The spot at the given position, relative to the top left corner of the board. The spacing between spots
+    /// doesn't belong to any spot.
+    pub fn spot_at(&self, pos: Vec2) -> Option<(u16, u16)> {
+        let x = (pos.x / self.pitch().x).floor();
+        let y = (pos.y / self.pitch().y).floor();
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return None;
+        }
+
+        let (x, y) = (x as u16, y as u16);
+
+        self.spot_rect(x, y).contains(pos.to_pos2()).then_some((x, y))
+    }
+
+    /// Columns and rows of the spots which are at least partially inside the given viewport (relative to the board)
+    pub fn visible_spots(&self, viewport: Rect) -> (Range<u16>, Range<u16>) {
+        let range = |min: f32, max: f32, pitch: f32, count: u16| {
+            let start = (min / pitch).floor().clamp(0.0, count as f32) as u16;
+            let end = (max / pitch).ceil().clamp(0.0, count as f32) as u16;
+
+            start..end.max(start)
+        };
+
+        (
+            range(viewport.min.x, viewport.max.x, self.pitch().x, self.width),
+            range(viewport.min.y, viewport.max.y, self.pitch().y, self.height),
+        )
+    }
+}
+
+/// The outcome of showing a board
+pub struct BoardResponse {
+    pub response: Response,
+
+    /// Spot under the pointer
+    pub hovered: Option<(u16, u16)>,
+
+    /// Spot which was clicked, and the button it was clicked with
+    pub clicked: Option<(u16, u16, PointerButton)>,
+}
+
+/// A whole board, painted as a single widget
+pub struct Board<F> {
+    layout: BoardLayout,
+    look: F,
+    interactive: bool,
+
+    /// Spot under the keyboard cursor
+    focused: Option<(u16, u16)>,
+}
+
+impl<F: Fn(u16, u16) -> SpotLook> Board<F> {
+    const FOCUS_COLOR: Color32 = Color32::YELLOW;
+
+    /// A board whose spots look as told by `look`, given their coordinates
+    pub fn new(layout: BoardLayout, look: F) -> Self {
+        Self { layout, look, interactive: true, focused: None }
+    }
+
+    /// Non interactive boards can't be clicked, and don't highlight the hovered spot
+    pub fn interactive(self, interactive: bool) -> Self {
+        Self { interactive, ..self }
+    }
+
+    /// Outline the spot under the keyboard cursor
+    pub fn focused(self, focused: Option<(u16, u16)>) -> Self {
+        Self { focused, ..self }
+    }
+
+    /// Add the board to the UI, at the cursor. Only the spots inside the `viewport` (relative to the board) are painted.
+    pub fn show(self, ui: &mut Ui, viewport: Rect) -> BoardResponse {
+        let sense = if self.interactive { Sense::click() } else { Sense::hover() };
+        let (rect, response) = ui.allocate_exact_size(self.layout.size(), sense);
+
+        let spot_at = |pos: Option<Pos2>| pos.and_then(|pos| self.layout.spot_at(pos - rect.min));
+        let hovered = spot_at(response.hover_pos()).filter(|_| self.interactive);
+        let clicked = [PointerButton::Primary, PointerButton::Secondary, PointerButton::Middle]
+            .into_iter()
+            .find(|&button| response.clicked_by(button))
+            .and_then(|button| spot_at(response.interact_pointer_pos()).map(|(x, y)| (x, y, button)));
+
+        if ui.is_rect_visible(rect) {
+            let visuals = ui.visuals();
+            let painter = ui.painter();
+            let font = FontId::monospace(self.layout.spot_size);
+            let fade = |color: Color32, enabled: bool| if enabled { color } else { tint_color_towards(color, visuals.window_fill()) };
+
+            let (xs, ys) = self.layout.visible_spots(viewport);
+            for y in ys {
+                for x in xs.clone() {
+                    let look = (self.look)(x, y);
+                    let spot_rect = self.layout.spot_rect(x, y).translate(rect.min.to_vec2());
+
+                    if look.raised {
+                        let widget = if look.enabled && hovered == Some((x, y)) { &visuals.widgets.hovered } else { &visuals.widgets.inactive };
+                        let fill = look.fill.unwrap_or(widget.bg_fill);
+                        painter.rect(spot_rect, widget.rounding, fade(fill, look.enabled), widget.bg_stroke);
+                    }
+
+                    if !look.text.trim().is_empty() {
+                        painter.text(spot_rect.center(), Align2::CENTER_CENTER, look.text, font.clone(), fade(look.color, look.enabled));
+                    }
+                }
+            }
+
+            if let Some((x, y)) = self.focused.filter(|&(x, y)| x < self.layout.width && y < self.layout.height) {
+                let spot_rect = self.layout.spot_rect(x, y).translate(rect.min.to_vec2());
+                painter.rect_stroke(spot_rect.expand(1.0), visuals.widgets.active.rounding, Stroke::new(2.0, Self::FOCUS_COLOR));
+            }
+        }
+
+        BoardResponse { response, hovered, clicked }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(width: u16, height: u16) -> BoardLayout {
+        BoardLayout { width, height, spot_size: 30.0, spacing: Vec2::new(8.0, 3.0) }
+    }
+
+    #[test]
+    fn geometry() {
+        let layout = layout(100, 100);
+        assert_eq!(layout.pitch(), Vec2::new(37.0, 35.0));
+        assert_eq!(layout.size(), Vec2::new(3700.0, 3500.0));
+        assert_eq!(layout.spot_rect(2, 1), Rect::from_min_size(Pos2::new(74.0, 35.0), Vec2::new(29.0, 32.0)));
+
+        // Spots partially in view are visible, and nothing outside the board
+        let viewport = Rect::from_min_size(Pos2::new(100.0, 0.0), Vec2::new(200.0, 100.0));
+        assert_eq!(layout.visible_spots(viewport), (2..9, 0..3));
+        let viewport = Rect::from_min_size(Pos2::new(3000.0, 3000.0), Vec2::new(2000.0, 2000.0));
+        assert_eq!(layout.visible_spots(viewport), (81..100, 85..100));
+        assert_eq!(self::layout(10, 10).visible_spots(viewport), (10..10, 10..10));
+    }
+
+    #[test]
+    fn hit_testing() {
+        let layout = layout(10, 5);
+        assert_eq!(layout.spot_at(Vec2::new(0.0, 0.0)), Some((0, 0)));
+        assert_eq!(layout.spot_at(Vec2::new(74.0 + 28.0, 35.0 + 31.0)), Some((2, 1)));
+        assert_eq!(layout.spot_at(Vec2::new(9.0 * 37.0 + 1.0, 4.0 * 35.0 + 1.0)), Some((9, 4)));
+
+        // Between spots, and outside the board
+        assert_eq!(layout.spot_at(Vec2::new(33.0, 10.0)), None);
+        assert_eq!(layout.spot_at(Vec2::new(10.0, 33.5)), None);
+        assert_eq!(layout.spot_at(Vec2::new(-1.0, 10.0)), None);
+        assert_eq!(layout.spot_at(Vec2::new(370.0, 10.0)), None);
+        assert_eq!(layout.spot_at(Vec2::new(10.0, 175.0)), None);
+    }
+}
@@ -0,0 +1,291 @@
+use crate::minefield;
+
+use minefield::{Minefield, SpotKind};
+use std::{collections::BTreeSet, fmt};
+
+/// Reasons for which a board can't be read or written in one of the community formats
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BoardFormatError {
+    /// The board data ends before all the mines were listed
+    Truncated,
+
+    /// The board is smaller than 3x1 spots, or too large for the format
+    Size { width: usize, height: usize },
+
+    /// The number of mines doesn't match the header
+    MineCount { expected: usize, found: usize },
+
+    /// The mine lies outside of the board
+    MineOutOfRange { x: usize, y: usize },
+
+    /// The mine was listed more than once
+    DuplicateMine { x: usize, y: usize },
+
+    /// The line of a text board can't be understood (lines are numbered from 1)
+    Syntax { line: usize },
+
+    /// The mines of the board have not been placed yet, so there is nothing to write
+    PendingMines,
+}
+
+impl fmt::Display for BoardFormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardFormatError::Truncated => write!(f, "The board data is incomplete"),
+            BoardFormatError::Size { width, height } => write!(f, "Unsupported board size {}x{}", width, height),
+            BoardFormatError::MineCount { expected, found } => {
+                write!(f, "Expected {} mines, but found {}", expected, found)
+            },
+            BoardFormatError::MineOutOfRange { x, y } => write!(f, "Mine at {}, {} is outside of the board", x, y),
+            BoardFormatError::DuplicateMine { x, y } => write!(f, "Mine at {}, {} is listed more than once", x, y),
+            BoardFormatError::Syntax { line } => write!(f, "Invalid board at line {}", line),
+            BoardFormatError::PendingMines => write!(f, "The mines have not been placed yet"),
+        }
+    }
+}
+
+impl std::error::Error for BoardFormatError {}
+
+/// Read a board in the binary Minesweeper Board Format: one byte each for the width and the height, the number of
+/// mines as a big-endian `u16`, and then one byte each for the x and y coordinates of every mine.
+pub fn read_mbf(data: &[u8]) -> Result<Minefield, BoardFormatError> {
+    if data.len() < 4 {
+        return Err(BoardFormatError::Truncated);
+    }
+
+    let width = data[0] as usize;
+    let height = data[1] as usize;
+    let mines = u16::from_be_bytes([data[2], data[3]]) as usize;
+
+    let coords = &data[4..];
+    if coords.len() < mines * 2 {
+        return Err(BoardFormatError::Truncated);
+    }
+    if coords.len() > mines * 2 {
+        return Err(BoardFormatError::MineCount { expected: mines, found: coords.len() / 2 });
+    }
+
+    let mines = coords.chunks(2).map(|xy| (xy[0] as usize, xy[1] as usize));
+
+    build_minefield(width, height, mines)
+}
+
+/// Write a board in the binary Minesweeper Board Format (see `read_mbf()`)
+pub fn write_mbf(minefield: &Minefield) -> Result<Vec<u8>, BoardFormatError> {
+    let width = minefield.width() as usize;
+    let height = minefield.height() as usize;
+    if width > u8::MAX as usize || height > u8::MAX as usize {
+        return Err(BoardFormatError::Size { width, height });
+    }
+
+    let mines = mine_coords(minefield)?;
+
+    let mut data = vec![width as u8, height as u8];
+    data.extend((mines.len() as u16).to_be_bytes());
+    for (x, y) in mines {
+        data.extend([x as u8, y as u8]);
+    }
+
+    Ok(data)
+}
+
+/// Read a board in the RAW text format: a header with the dimensions and mine count, followed by the board itself,
+/// with one line per row, `*` for mines and `0` for empty spots. For example:
+///
+/// ```text
+/// Width: 3
+/// Height: 2
+/// Mines: 1
+/// Board:
+/// 00*
+/// 000
+/// ```
+pub fn read_raw(text: &str) -> Result<Minefield, BoardFormatError> {
+    let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())).filter(|(_, line)| !line.is_empty());
+
+    let width = parse_header(lines.next(), "Width")?;
+    let height = parse_header(lines.next(), "Height")?;
+    let mines = parse_header(lines.next(), "Mines")?;
+
+    match lines.next() {
+        Some((_, "Board:")) => {},
+        Some((line, _)) => return Err(BoardFormatError::Syntax { line }),
+        None => return Err(BoardFormatError::Truncated),
+    }
+
+    if width < 3 || height < 1 || width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(BoardFormatError::Size { width, height });
+    }
+
+    let mut mine_coords = vec![];
+    for y in 0..height {
+        let (line, row) = lines.next().ok_or(BoardFormatError::Truncated)?;
+        if row.chars().count() != width {
+            return Err(BoardFormatError::Syntax { line });
+        }
+
+        for (x, symbol) in row.chars().enumerate() {
+            match symbol {
+                '*' => mine_coords.push((x, y)),
+                '0' => {},
+                _ => return Err(BoardFormatError::Syntax { line }),
+            }
+        }
+    }
+
+    if let Some((line, _)) = lines.next() {
+        return Err(BoardFormatError::Syntax { line });
+    }
+
+    if mine_coords.len() != mines {
+        return Err(BoardFormatError::MineCount { expected: mines, found: mine_coords.len() });
+    }
+
+    build_minefield(width, height, mine_coords.into_iter())
+}
+
+/// Write a board in the RAW text format (see `read_raw()`)
+pub fn write_raw(minefield: &Minefield) -> Result<String, BoardFormatError> {
+    let mines = mine_coords(minefield)?;
+
+    let mut text = format!(
+        "Width: {}\nHeight: {}\nMines: {}\nBoard:\n",
+        minefield.width(),
+        minefield.height(),
+        mines.len()
+    );
+
+    for y in 0..minefield.height() {
+        for x in 0..minefield.width() {
+            let is_mine = minefield.spot(x, y).map(|spot| spot.kind()) == Some(SpotKind::Mine);
+            text.push(if is_mine { '*' } else { '0' });
+        }
+        text.push('\n');
+    }
+
+    Ok(text)
+}
+
+/// Parse a `Name: value` header line of a RAW board
+fn parse_header(line: Option<(usize, &str)>, name: &str) -> Result<usize, BoardFormatError> {
+    let (line, text) = line.ok_or(BoardFormatError::Truncated)?;
+
+    text.strip_prefix(name)
+        .and_then(|text| text.strip_prefix(':'))
+        .and_then(|value| value.trim().parse().ok())
+        .ok_or(BoardFormatError::Syntax { line })
+}
+
+/// Build a minefield with mines at the given coordinates, checking them beforehand
+pub(crate) fn build_minefield(
+    width: usize,
+    height: usize,
+    mines: impl Iterator<Item = (usize, usize)>,
+) -> Result<Minefield, BoardFormatError> {
+    // `Minefield::new()` would silently enlarge a board which is too small
+    if width < 3 || height < 1 {
+        return Err(BoardFormatError::Size { width, height });
+    }
+
+    let mut coords = BTreeSet::new();
+    for (x, y) in mines {
+        if x >= width || y >= height {
+            return Err(BoardFormatError::MineOutOfRange { x, y });
+        }
+        if !coords.insert((x as u16, y as u16)) {
+            return Err(BoardFormatError::DuplicateMine { x, y });
+        }
+    }
+
+    let coords: Vec<(u16, u16)> = coords.into_iter().collect();
+
+    Ok(Minefield::new(width as u16, height as u16).with_mines_at(&coords))
+}
+
+/// Coordinates of the mines of a minefield, row by row
+pub(crate) fn mine_coords(minefield: &Minefield) -> Result<Vec<(u16, u16)>, BoardFormatError> {
+    if minefield.has_pending_mines() {
+        return Err(BoardFormatError::PendingMines);
+    }
+
+    Ok((0..minefield.height())
+        .flat_map(|y| (0..minefield.width()).map(move |x| (x, y)))
+        .filter(|&(x, y)| minefield.spot(x, y).map(|spot| spot.kind()) == Some(SpotKind::Mine))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minefield::FirstStepPolicy;
+
+    fn same_layout(a: &Minefield, b: &Minefield) -> bool {
+        a.width() == b.width()
+            && a.height() == b.height()
+            && a.mines() == b.mines()
+            && (0..a.height()).all(|y| (0..a.width()).all(|x| a.spot(x, y).unwrap().kind() == b.spot(x, y).unwrap().kind()))
+    }
+
+    #[test]
+    fn mbf_round_trip() {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        let data = write_mbf(&minefield).unwrap();
+        assert_eq!(data, vec![3, 4, 0, 2, 2, 0, 0, 3]);
+        assert!(same_layout(&read_mbf(&data).unwrap(), &minefield));
+
+        let minefield = Minefield::new(30, 16).with_mines_seeded(99, 42);
+        assert!(same_layout(&read_mbf(&write_mbf(&minefield).unwrap()).unwrap(), &minefield));
+    }
+
+    #[test]
+    fn raw_round_trip() {
+        let minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        let text = write_raw(&minefield).unwrap();
+        assert_eq!(text, "Width: 3\nHeight: 4\nMines: 2\nBoard:\n00*\n000\n000\n*00\n");
+        assert!(same_layout(&read_raw(&text).unwrap(), &minefield));
+
+        let minefield = Minefield::new(16, 16).with_mines_seeded(40, 7);
+        assert!(same_layout(&read_raw(&write_raw(&minefield).unwrap()).unwrap(), &minefield));
+    }
+
+    #[test]
+    fn invalid_mbf() {
+        assert_eq!(read_mbf(&[3, 4, 0]).unwrap_err(), BoardFormatError::Truncated);
+        assert_eq!(read_mbf(&[3, 4, 0, 2, 2, 0]).unwrap_err(), BoardFormatError::Truncated);
+        assert_eq!(read_mbf(&[3, 4, 0, 1, 2, 0, 1, 1]).unwrap_err(), BoardFormatError::MineCount { expected: 1, found: 2 });
+        assert_eq!(read_mbf(&[2, 4, 0, 0]).unwrap_err(), BoardFormatError::Size { width: 2, height: 4 });
+        assert_eq!(read_mbf(&[3, 4, 0, 1, 3, 0]).unwrap_err(), BoardFormatError::MineOutOfRange { x: 3, y: 0 });
+        assert_eq!(read_mbf(&[3, 4, 0, 2, 1, 1, 1, 1]).unwrap_err(), BoardFormatError::DuplicateMine { x: 1, y: 1 });
+
+        let too_wide = Minefield::new(256, 1).with_mines_at(&[(0, 0)]);
+        assert_eq!(write_mbf(&too_wide), Err(BoardFormatError::Size { width: 256, height: 1 }));
+
+        let pending = Minefield::new(9, 9).with_mines_deferred(10, 1, FirstStepPolicy::SafeSpot);
+        assert_eq!(write_mbf(&pending), Err(BoardFormatError::PendingMines));
+        assert_eq!(write_raw(&pending), Err(BoardFormatError::PendingMines));
+    }
+
+    #[test]
+    fn invalid_raw() {
+        assert_eq!(read_raw("").unwrap_err(), BoardFormatError::Truncated);
+        assert_eq!(read_raw("Width: 3\nHeight: x\n").unwrap_err(), BoardFormatError::Syntax { line: 2 });
+        assert_eq!(read_raw("Width: 3\nHeight: 1\nMines: 1\n00*\n").unwrap_err(), BoardFormatError::Syntax { line: 4 });
+        assert_eq!(read_raw("Width: 3\nHeight: 2\nMines: 1\nBoard:\n00*\n").unwrap_err(), BoardFormatError::Truncated);
+        assert_eq!(read_raw("Width: 3\nHeight: 1\nMines: 1\nBoard:\n0*\n").unwrap_err(), BoardFormatError::Syntax { line: 5 });
+        assert_eq!(read_raw("Width: 3\nHeight: 1\nMines: 1\nBoard:\n0x*\n").unwrap_err(), BoardFormatError::Syntax { line: 5 });
+        assert_eq!(read_raw("Width: 3\nHeight: 1\nMines: 1\nBoard:\n00*\n000\n").unwrap_err(), BoardFormatError::Syntax { line: 6 });
+        assert_eq!(
+            read_raw("Width: 3\nHeight: 1\nMines: 2\nBoard:\n00*\n").unwrap_err(),
+            BoardFormatError::MineCount { expected: 2, found: 1 }
+        );
+        assert_eq!(
+            read_raw("Width: 1\nHeight: 1\nMines: 0\nBoard:\n0\n").unwrap_err(),
+            BoardFormatError::Size { width: 1, height: 1 }
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Headless mode, which plays a game with commands read from stdin, one per line:
+//!
+//! ```text
+//! step 3 4
+//! flag 0 0
+//! chord 2 2
+//! show
+//! ```
+//!
+//! Every command gets a response line (or the board, for `show`), either as text or, with `--json`, as a JSON object.
+//! Responses are written to stdout, and errors to stderr.
+
+use crate::{clock, game, minefield, session};
+
+use clock::Clock;
+use game::{GameConfig, GameDifficulty, GameState};
+use minefield::{Minefield, StepResult};
+use serde_json::json;
+use session::{GameSession, MoveOutcome};
+use std::{
+    io::{self, BufRead, Write},
+    str::FromStr,
+};
+
+pub const USAGE: &str = "usage: minesweep-rs --cli [--json] [--difficulty easy|medium|hard] [--seed N | --board FILE]";
+
+/// A command read from the input
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Command {
+    Step(u16, u16),
+    Flag(u16, u16),
+    Chord(u16, u16),
+
+    /// Print the board
+    Show,
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Step(..) => "step",
+            Command::Flag(..) => "flag",
+            Command::Chord(..) => "chord",
+            Command::Show => "show",
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        let coordinates = || match words[1..] {
+            [x, y] => match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Ok((x, y)),
+                _ => Err(format!("invalid coordinates `{} {}`", x, y)),
+            },
+            _ => Err(format!("`{}` takes the x and y coordinates of a spot", words[0])),
+        };
+
+        match words.first() {
+            Some(&"step") => coordinates().map(|(x, y)| Command::Step(x, y)),
+            Some(&"flag") => coordinates().map(|(x, y)| Command::Flag(x, y)),
+            Some(&"chord") => coordinates().map(|(x, y)| Command::Chord(x, y)),
+            Some(&"show") if words.len() == 1 => Ok(Command::Show),
+            Some(&"show") => Err("`show` takes no arguments".to_string()),
+            Some(word) => Err(format!("unknown command `{}`", word)),
+            None => Err("empty command".to_string()),
+        }
+    }
+}
+
+/// Options of the headless mode, given on the command line
+#[derive(Clone, Debug, Default)]
+pub struct CliOptions {
+    /// Respond in JSON rather than text
+    pub json: bool,
+
+    /// Configuration of a new, random, minefield
+    pub config: GameConfig,
+
+    /// Seed of the random minefield, random if not given
+    pub seed: Option<u64>,
+
+    /// Minefield to play instead of a random one, in the minefield notation
+    pub board: Option<Minefield>,
+}
+
+impl CliOptions {
+    /// Parse the command line arguments, without the program name
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut is_random_board = false;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("`{}` takes a value", arg));
+
+            match arg.as_str() {
+                "--cli" => {},
+                "--json" => options.json = true,
+                "--seed" => {
+                    is_random_board = true;
+                    let seed = value()?;
+                    options.seed = Some(seed.parse().map_err(|_| format!("invalid seed `{}`", seed))?);
+                },
+                "--difficulty" => {
+                    is_random_board = true;
+                    options.config = match value()?.as_str() {
+                        "easy" => GameDifficulty::EASY,
+                        "medium" => GameDifficulty::MEDIUM,
+                        "hard" => GameDifficulty::HARD,
+                        difficulty => return Err(format!("unknown difficulty `{}`", difficulty)),
+                    };
+                },
+                "--board" => {
+                    let path = value()?;
+                    let notation = std::fs::read_to_string(path).map_err(|e| format!("could not read `{}`: {}", path, e))?;
+                    let board = notation.parse().map_err(|e| format!("invalid board in `{}`: {}", path, e))?;
+                    options.board = Some(board);
+                },
+                _ => return Err(format!("unknown option `{}`", arg)),
+            }
+        }
+
+        // The seed and difficulty only make a random board
+        if options.board.is_some() && is_random_board {
+            return Err("`--board` can't be combined with `--seed` or `--difficulty`".to_string());
+        }
+
+        Ok(options)
+    }
+}
+
+/// A game played with commands
+pub struct Cli {
+    session: GameSession,
+    json: bool,
+}
+
+impl Cli {
+    pub fn new(options: CliOptions) -> Self {
+        let minefield = match options.board {
+            Some(board) => board,
+            None => options.config.minefield(options.seed.unwrap_or_else(rand::random)),
+        };
+
+        Self { session: GameSession::new(minefield), json: options.json }
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.session = self.session.with_clock(clock);
+        self
+    }
+
+    pub fn session(&self) -> &GameSession {
+        &self.session
+    }
+
+    /// Run a line of input, and return the response to it, if any. Blank lines and `#` comments get no response.
+    pub fn run_line(&mut self, line: &str) -> Option<Result<String, String>> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        Some(match line.parse::<Command>() {
+            Ok(command) => self.run(command),
+            Err(e) if self.json => Err(json!({ "command": line, "error": e }).to_string()),
+            Err(e) => Err(format!("error: {}", e)),
+        })
+    }
+
+    /// Run a command, and return the response to it. Moves off the board are errors.
+    pub fn run(&mut self, command: Command) -> Result<String, String> {
+        let (x, y) = match command {
+            Command::Step(x, y) | Command::Flag(x, y) | Command::Chord(x, y) => (x, y),
+            Command::Show => return Ok(self.show()),
+        };
+
+        let minefield = self.session.minefield();
+        if x >= minefield.width() || y >= minefield.height() {
+            let e = format!("spot {} {} is off the {}x{} board", x, y, minefield.width(), minefield.height());
+            return Err(if self.json {
+                json!({ "command": command.name(), "x": x, "y": y, "error": e }).to_string()
+            } else {
+                format!("error: {}", e)
+            });
+        }
+
+        let outcome = match command {
+            Command::Step(x, y) => self.session.step(x, y),
+            Command::Flag(x, y) => self.session.flag(x, y),
+            Command::Chord(x, y) => self.session.chord(x, y),
+            Command::Show => unreachable!(),
+        };
+        let result = Self::result_name(command, outcome);
+
+        if self.json {
+            Ok(json!({
+                "command": command.name(),
+                "x": x,
+                "y": y,
+                "result": result,
+                "state": self.state_name(),
+                "flags": self.session.placed_flags(),
+            }).to_string())
+        } else {
+            let ending = match outcome.and_then(|outcome| outcome.ended()) {
+                Some(true) => ", game won",
+                Some(false) => ", game lost",
+                None => "",
+            };
+            Ok(format!("{} {} {}: {}{}", command.name(), x, y, result, ending))
+        }
+    }
+
+    /// What a move did: `phew`, `boom` or `invalid` for steps and chords, `flagged` or `unflagged` for flags, and
+    /// `ignored` for moves the game didn't take
+    fn result_name(command: Command, outcome: Option<MoveOutcome>) -> &'static str {
+        match (command, outcome) {
+            (_, None) => "ignored",
+            (Command::Flag(..), Some(outcome)) if outcome.flags_change > 0 => "flagged",
+            (Command::Flag(..), Some(_)) => "unflagged",
+            (_, Some(outcome)) => match outcome.step_result {
+                StepResult::Phew => "phew",
+                StepResult::Boom => "boom",
+                StepResult::Invalid => "invalid",
+            },
+        }
+    }
+
+    fn state_name(&self) -> &'static str {
+        match self.session.state() {
+            GameState::Ready => "ready",
+            GameState::Running if self.session.is_paused() => "paused",
+            GameState::Running => "running",
+            GameState::Stopped(true) => "won",
+            GameState::Stopped(false) => "lost",
+        }
+    }
+
+    /// The board, and the state of the game
+    fn show(&self) -> String {
+        let minefield = self.session.minefield();
+        let elapsed = self.session.elapsed();
+
+        if self.json {
+            let board: Vec<Vec<String>> = (0..minefield.height())
+                .map(|y| {
+                    (0..minefield.width())
+                        .map(|x| minefield.spot(x, y).unwrap().view_symbol().to_string())
+                        .collect()
+                })
+                .collect();
+
+            json!({
+                "command": "show",
+                "width": minefield.width(),
+                "height": minefield.height(),
+                "mines": minefield.mines(),
+                "flags": self.session.placed_flags(),
+                "state": self.state_name(),
+                // Seconds, with millisecond precision
+                "elapsed": elapsed.as_millis() as f64 / 1000.0,
+                "board": board,
+            }).to_string()
+        } else {
+            format!(
+                "{}{}, flags {}/{}, time {}",
+                minefield.state_view(),
+                self.state_name(),
+                self.session.placed_flags(),
+                minefield.mines(),
+                clock::format_seconds(elapsed),
+            )
+        }
+    }
+
+    /// Run every line of the input, and write the responses to the output, and the errors to the error output. Returns
+    /// whether all commands were valid.
+    pub fn run_all(&mut self, input: impl BufRead, mut output: impl Write, mut errors: impl Write) -> io::Result<bool> {
+        let mut all_valid = true;
+
+        for line in input.lines() {
+            match self.run_line(&line?) {
+                Some(Ok(response)) => {
+                    writeln!(output, "{}", response)?;
+                    output.flush()?;
+                },
+                Some(Err(error)) => {
+                    all_valid = false;
+                    writeln!(errors, "{}", error)?;
+                    errors.flush()?;
+                },
+                None => {},
+            }
+        }
+
+        Ok(all_valid)
+    }
+}
+
+/// Run the headless mode with the given command line arguments (without the program name) on stdin and stdout, and
+/// return the exit code of the process
+pub fn main(args: &[String]) -> i32 {
+    let options = match CliOptions::parse(args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n{}", e, USAGE);
+            return 2;
+        },
+    };
+
+    match Cli::new(options).run_all(io::stdin().lock(), io::stdout().lock(), io::stderr().lock()) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::FakeClock;
+    use serde_json::Value;
+    use std::time::Duration;
+
+    fn cli(json: bool) -> (Cli, FakeClock) {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let board = "
+            . . *
+            . . .
+            . . .
+            * . .
+        ";
+        let options = CliOptions { json, board: Some(board.parse().unwrap()), ..Default::default() };
+        let clock = FakeClock::default();
+
+        (Cli::new(options).with_clock(clock.clone()), clock)
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!("step 3 4".parse(), Ok(Command::Step(3, 4)));
+        assert_eq!("  flag 0 0 ".parse(), Ok(Command::Flag(0, 0)));
+        assert_eq!("chord 2 2".parse(), Ok(Command::Chord(2, 2)));
+        assert_eq!("show".parse(), Ok(Command::Show));
+
+        assert!("step 3".parse::<Command>().is_err());
+        assert!("step -1 2".parse::<Command>().is_err());
+        assert!("show 1 1".parse::<Command>().is_err());
+        assert!("dig 1 1".parse::<Command>().is_err());
+    }
+
+    #[test]
+    fn parse_options() {
+        let args = |args: &str| args.split_whitespace().map(str::to_string).collect::<Vec<String>>();
+
+        let options = CliOptions::parse(&args("--cli --json --difficulty hard --seed 42")).unwrap();
+        assert!(options.json);
+        assert_eq!(options.config, GameDifficulty::HARD);
+        assert_eq!(options.seed, Some(42));
+
+        assert!(CliOptions::parse(&args("--seed")).is_err());
+        assert!(CliOptions::parse(&args("--seed x")).is_err());
+        assert!(CliOptions::parse(&args("--difficulty extreme")).is_err());
+        assert!(CliOptions::parse(&args("--window")).is_err());
+
+        // A board given in a file can't be random too
+        let path = std::env::temp_dir().join(format!("minesweep-rs-cli-{}.txt", std::process::id()));
+        std::fs::write(&path, ". . *\n. . .\n").unwrap();
+        let board = path.to_string_lossy();
+        assert_eq!(CliOptions::parse(&args(&format!("--board {}", board))).unwrap().board.unwrap().mines(), 1);
+        assert!(CliOptions::parse(&args(&format!("--board {} --seed 1", board))).is_err());
+        assert!(CliOptions::parse(&args(&format!("--difficulty easy --board {}", board))).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn text_game() {
+        let (mut cli, clock) = cli(false);
+
+        assert_eq!(cli.run_line("# comment"), None);
+        assert_eq!(cli.run_line(""), None);
+        assert_eq!(cli.run_line("step 0 0"), Some(Ok("step 0 0: phew".to_string())));
+        assert_eq!(cli.run_line("step 0 0"), Some(Ok("step 0 0: ignored".to_string())));
+        assert_eq!(cli.run_line("flag 2 0"), Some(Ok("flag 2 0: flagged".to_string())));
+        assert_eq!(cli.run_line("step 5 0"), Some(Err("error: spot 5 0 is off the 3x4 board".to_string())));
+        assert_eq!(cli.run_line("jump"), Some(Err("error: unknown command `jump`".to_string())));
+
+        clock.advance(Duration::from_millis(1_250));
+        let expected = [
+            "    0 1 2",
+            "0 [   1 ⚐ ]",
+            "1 [   1 • ]",
+            "2 [ 1 1 • ]",
+            "3 [ • • • ]",
+            "running, flags 1/2, time 1.250",
+        ];
+        assert_eq!(cli.run_line("show"), Some(Ok(expected.join("\n"))));
+
+        assert_eq!(cli.run_line("step 1 3"), Some(Ok("step 1 3: phew".to_string())));
+        assert_eq!(cli.run_line("step 0 3"), Some(Ok("step 0 3: boom, game lost".to_string())));
+        assert_eq!(cli.session().state(), GameState::Stopped(false));
+    }
+
+    #[test]
+    fn json_game() {
+        let (mut cli, _) = cli(true);
+
+        let response: Value = serde_json::from_str(&cli.run(Command::Step(0, 0)).unwrap()).unwrap();
+        assert_eq!(response, json!({ "command": "step", "x": 0, "y": 0, "result": "phew", "state": "running", "flags": 0 }));
+
+        for command in [Command::Flag(2, 0), Command::Flag(0, 3), Command::Step(2, 2), Command::Chord(1, 2)] {
+            cli.run(command).unwrap();
+        }
+        assert_eq!(cli.session().state(), GameState::Stopped(true));
+
+        let response: Value = serde_json::from_str(&cli.run(Command::Show).unwrap()).unwrap();
+        assert_eq!(response["state"], "won");
+        assert_eq!(response["flags"], 2);
+        assert_eq!(response["board"][0], json!([" ", "1", "⚐"]));
+        assert_eq!(response["board"][3], json!(["⚐", "1", " "]));
+
+        let error: Value = serde_json::from_str(&cli.run_line("step x y").unwrap().unwrap_err()).unwrap();
+        assert_eq!(error["error"], "invalid coordinates `x y`");
+    }
+
+    #[test]
+    fn run_all() {
+        let (mut cli, _) = cli(false);
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+
+        let all_valid = cli.run_all("step 0 0\n\nflag 2 0\n".as_bytes(), &mut output, &mut errors).unwrap();
+        assert!(all_valid);
+        assert_eq!(String::from_utf8(output).unwrap(), "step 0 0: phew\nflag 2 0: flagged\n");
+        assert!(errors.is_empty());
+
+        // Errors are kept apart from the responses
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        let all_valid = cli.run_all("step 9 9\nflag 0 3\n".as_bytes(), &mut output, &mut errors).unwrap();
+        assert!(!all_valid);
+        assert_eq!(String::from_utf8(output).unwrap(), "flag 0 3: flagged\n");
+        assert_eq!(String::from_utf8(errors).unwrap(), "error: spot 9 9 is off the 3x4 board\n");
+    }
+}
@@ -0,0 +1,137 @@
+use instant::Instant;
+use std::{cell::Cell, fmt, rc::Rc, time::Duration};
+
+/// A source of time, which never goes backwards
+pub trait Clock: fmt::Debug {
+    /// Time since a fixed, arbitrary, point in the past
+    fn now(&self) -> Duration;
+}
+
+/// The monotonic clock of the system (on the web, the high resolution time of the page)
+#[derive(Copy, Clone, Debug)]
+pub struct MonotonicClock {
+    origin: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> Self {
+        Self { origin: Instant::now() }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// A clock which only moves when told to, for tests. Clones of a clock share its time, so that a test can move the
+/// clock it handed over to the code being tested.
+#[derive(Clone, Debug, Default)]
+pub struct FakeClock {
+    now: Rc<Cell<Duration>>,
+}
+
+impl FakeClock {
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+/// Time counted on a clock, only while running
+#[derive(Clone, Debug)]
+pub struct Stopwatch {
+    clock: Rc<dyn Clock>,
+
+    /// Time counted before the stopwatch was last started
+    elapsed: Duration,
+
+    /// Time of the clock when the stopwatch was last started, if it is running
+    started_at: Option<Duration>,
+}
+
+impl Stopwatch {
+    /// A stopped stopwatch, which already counted the given time
+    pub fn new(clock: Rc<dyn Clock>, elapsed: Duration) -> Self {
+        Self { clock, elapsed, started_at: None }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some()
+    }
+
+    /// Start counting, from where the stopwatch was stopped
+    pub fn start(&mut self) {
+        if self.started_at.is_none() {
+            self.started_at = Some(self.clock.now());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.elapsed = self.elapsed();
+        self.started_at = None;
+    }
+
+    /// Time counted so far
+    pub fn elapsed(&self) -> Duration {
+        match self.started_at {
+            Some(started_at) => self.elapsed + self.clock.now().saturating_sub(started_at),
+            None => self.elapsed,
+        }
+    }
+}
+
+/// Format a duration as seconds with millisecond precision, e.g. `12.345`
+pub fn format_seconds(duration: Duration) -> String {
+    format!("{}.{:03}", duration.as_secs(), duration.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopwatch() {
+        let clock = FakeClock::default();
+        clock.advance(Duration::from_secs(100));
+
+        let mut stopwatch = Stopwatch::new(Rc::new(clock.clone()), Duration::from_millis(1_500));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(stopwatch.elapsed(), Duration::from_millis(1_500));
+
+        // Only time spent running counts
+        stopwatch.start();
+        clock.advance(Duration::from_millis(250));
+        stopwatch.start();
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed(), Duration::from_millis(1_750));
+
+        stopwatch.stop();
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(stopwatch.elapsed(), Duration::from_millis(1_750));
+
+        stopwatch.start();
+        clock.advance(Duration::from_millis(10_345));
+        assert_eq!(format_seconds(stopwatch.elapsed()), "12.095");
+        assert_eq!(format_seconds(Duration::from_millis(7)), "0.007");
+    }
+
+    #[test]
+    fn monotonic_clock() {
+        let clock = MonotonicClock::new();
+        let earlier = clock.now();
+        assert!(clock.now() >= earlier);
+    }
+}
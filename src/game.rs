@@ -0,0 +1,216 @@
+use crate::{minefield, metrics};
+
+use minefield::{Minefield, FirstStepPolicy, NoGuessBudget};
+use metrics::Metrics;
+use serde::{Serialize, Deserialize};
+use std::time::Duration;
+
+/// Current state of the game
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum GameState {
+    /// Game is ready to start running
+    #[default]
+    Ready,
+
+    /// Game is running
+    Running,
+
+    /// Game is stopped, and was either won (`true`), or lost (`false`)
+    Stopped(bool)
+}
+
+/// Outcome of a finished game
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct GameResult {
+    pub is_won: bool,
+
+    /// Play time
+    pub elapsed: Duration,
+
+    /// Number of hints used during the game
+    pub hints: u32,
+
+    /// Number of moves undone during the game
+    pub undos: u32,
+
+    /// Efficiency of the play, if the mines were placed
+    pub metrics: Option<Metrics>,
+}
+
+impl GameResult {
+    /// Check if the player was helped during the game, which ranks it below unassisted games in the high scores
+    pub fn is_assisted(&self) -> bool {
+        self.hints > 0 || self.undos > 0
+    }
+
+    /// One line summary of the metrics of the game: all of them for a won game, the 3BV and clicks for a lost one
+    pub fn summary(&self) -> Option<String> {
+        let metrics = self.metrics?;
+        if !self.is_won {
+            return Some(format!("3BV {} · {} clicks", metrics.bbbv, metrics.clicks.total()));
+        }
+
+        let seconds = self.elapsed.as_secs_f64();
+        let format = |value: Option<f64>, precision: usize| {
+            value.map(|value| format!("{:.*}", precision, value)).unwrap_or_else(|| "-".to_string())
+        };
+
+        Some(format!(
+            "3BV {} · 3BV/s {} · IOS {} · RQP {} · Efficiency {}",
+            metrics.bbbv,
+            format(metrics.bbbv_per_second(seconds), 2),
+            format(metrics.ios(seconds), 2),
+            format(metrics.rqp(seconds), 1),
+            metrics.efficiency().map(|efficiency| format!("{:.0}%", efficiency * 100.0)).unwrap_or_else(|| "-".to_string()),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GameConfig {
+    pub width: u16,
+    pub height: u16,
+    pub mines: u16,
+
+    /// How the first step is protected from hitting a mine
+    #[serde(default)]
+    pub first_step: FirstStepPolicy,
+
+    /// Only generate boards which can be cleared without guessing
+    #[serde(default)]
+    pub no_guess: bool,
+
+    /// Limits on the search for a board which can be cleared without guessing
+    #[serde(default)]
+    pub no_guess_budget: NoGuessBudget,
+}
+
+impl GameConfig {
+    /// Narrowest board, as enforced by `Minefield::new()`
+    pub const MIN_WIDTH: u16 = 3;
+    pub const MIN_HEIGHT: u16 = 1;
+    pub const MAX_WIDTH: u16 = 200;
+    pub const MAX_HEIGHT: u16 = 200;
+
+    /// Most mines which can be placed on the board, leaving at least one spot to step on
+    pub fn max_mines(&self) -> u16 {
+        (self.width as u32 * self.height as u32 - 1).min(u16::MAX as u32) as u16
+    }
+
+    /// Check that the board size and mine count are within the limits
+    pub fn is_valid(&self) -> bool {
+        (Self::MIN_WIDTH..=Self::MAX_WIDTH).contains(&self.width)
+            && (Self::MIN_HEIGHT..=Self::MAX_HEIGHT).contains(&self.height)
+            && (1..=self.max_mines()).contains(&self.mines)
+    }
+
+    /// The same config, with the board size and mine count brought within the limits
+    pub fn validated(mut self) -> Self {
+        self.width = self.width.clamp(Self::MIN_WIDTH, Self::MAX_WIDTH);
+        self.height = self.height.clamp(Self::MIN_HEIGHT, Self::MAX_HEIGHT);
+        self.mines = self.mines.clamp(1, self.max_mines());
+
+        self
+    }
+
+    /// Create a new minefield matching this config, with the mines placed according to the given seed
+    pub fn minefield(&self, seed: u64) -> Minefield {
+        let minefield = Minefield::new(self.width, self.height);
+
+        if self.no_guess {
+            minefield.with_mines_no_guess(self.mines, seed, self.no_guess_budget)
+        } else {
+            minefield.with_mines_deferred(self.mines, seed, self.first_step)
+        }
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameDifficulty::EASY
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameDifficulty {
+    Easy,
+    Medium,
+    Hard,
+
+    /// Any other board size or mine count
+    Custom,
+}
+
+impl GameDifficulty {
+    pub const EASY: GameConfig = GameConfig {
+        width: 10, height: 10, mines: 10, first_step: FirstStepPolicy::SafeSpot, no_guess: false,
+        no_guess_budget: NoGuessBudget::DEFAULT,
+    };
+    pub const MEDIUM: GameConfig = GameConfig {
+        width: 16, height: 16, mines: 40, first_step: FirstStepPolicy::SafeSpot, no_guess: false,
+        no_guess_budget: NoGuessBudget::DEFAULT,
+    };
+    pub const HARD: GameConfig = GameConfig {
+        width: 30, height: 16, mines: 99, first_step: FirstStepPolicy::SafeSpot, no_guess: false,
+        no_guess_budget: NoGuessBudget::DEFAULT,
+    };
+
+    pub fn from_config(config: &GameConfig) -> Self {
+        Self::matching(config).unwrap_or(Self::Custom)
+    }
+
+    /// The standard difficulty with the same board as the given config, if any
+    pub fn matching(config: &GameConfig) -> Option<Self> {
+        // Only the board size and mine count define the difficulty
+        let board = |config: &GameConfig| (config.width, config.height, config.mines);
+
+        if board(config) == board(&Self::EASY) {
+            Some(Self::Easy)
+        } else if board(config) == board(&Self::MEDIUM) {
+            Some(Self::Medium)
+        } else if board(config) == board(&Self::HARD) {
+            Some(Self::Hard)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_difficulty() {
+        assert_eq!(GameDifficulty::from_config(&GameDifficulty::HARD), GameDifficulty::Hard);
+
+        let custom = GameConfig { width: 20, height: 10, mines: 30, ..Default::default() };
+        assert_eq!(GameDifficulty::from_config(&custom), GameDifficulty::Custom);
+        assert!(custom.is_valid());
+        assert_eq!(custom.validated(), custom);
+
+        // Out of range configs, e.g. edited by hand, are brought back within the limits
+        let invalid = GameConfig { width: 0, height: 500, mines: 0, ..Default::default() };
+        assert!(!invalid.is_valid());
+        let valid = invalid.validated();
+        assert_eq!((valid.width, valid.height, valid.mines), (GameConfig::MIN_WIDTH, GameConfig::MAX_HEIGHT, 1));
+
+        let crowded = GameConfig { width: 3, height: 1, mines: 10, ..Default::default() }.validated();
+        assert_eq!(crowded.mines, 2);
+        assert!(crowded.is_valid());
+    }
+
+    #[test]
+    fn no_guess_budget() {
+        // Configs stored before the budget could be set get the default one
+        let json = r#"{"width": 20, "height": 10, "mines": 30, "no_guess": true}"#;
+        let config: GameConfig = serde_json::from_str(json).unwrap();
+        assert_eq!(config.no_guess_budget, NoGuessBudget::default());
+
+        // A board is still generated when the budget runs out, just not necessarily one without guessing
+        let config = GameConfig { no_guess_budget: NoGuessBudget { attempts: 1 }, ..config };
+        let mut minefield = config.minefield(7);
+        minefield.step(5, 5);
+        assert_eq!(minefield.mines(), 30);
+    }
+}
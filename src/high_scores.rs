@@ -0,0 +1,240 @@
+use crate::{game, metrics, minefield};
+
+use game::{GameConfig, GameDifficulty};
+use metrics::Metrics;
+use minefield::Minefield;
+use serde::{Serialize, Deserialize};
+use std::{fmt, time::Duration};
+
+/// The board a game was played on. High scores are kept separately for each board.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub struct ScoreBoard {
+    pub width: u16,
+    pub height: u16,
+    pub mines: u16,
+
+    /// The mines were placed such that the board can be cleared without guessing, which makes it easier
+    #[serde(default)]
+    pub no_guess: bool,
+}
+
+impl ScoreBoard {
+    /// The board of a minefield
+    pub fn of_minefield(minefield: &Minefield) -> Self {
+        Self { width: minefield.width(), height: minefield.height(), mines: minefield.mines(), no_guess: minefield.is_no_guess() }
+    }
+
+    /// The standard difficulty with this board, if any
+    pub fn difficulty(&self) -> Option<GameDifficulty> {
+        GameDifficulty::matching(&GameConfig { width: self.width, height: self.height, mines: self.mines, ..Default::default() })
+    }
+}
+
+impl From<&GameConfig> for ScoreBoard {
+    fn from(config: &GameConfig) -> Self {
+        Self { width: config.width, height: config.height, mines: config.mines, no_guess: config.no_guess }
+    }
+}
+
+impl fmt::Display for ScoreBoard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.difficulty() {
+            Some(difficulty) => write!(f, "{:?}", difficulty)?,
+            None => write!(f, "Custom {}x{}, {} mines", self.width, self.height, self.mines)?,
+        }
+
+        if self.no_guess {
+            write!(f, ", no guessing")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A won game, as kept in the high scores
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    /// Play time, in whole seconds
+    pub seconds: i32,
+
+    /// Play time with millisecond precision, missing from scores recorded before it was counted
+    #[serde(default)]
+    pub millis: Option<u64>,
+
+    /// When the game was won, in a human readable form
+    pub date: String,
+
+    /// Seed of the minefield, so that the game can be played again
+    pub seed: u64,
+
+    /// The player used hints or undid moves
+    pub assisted: bool,
+
+    /// Efficiency of the play, missing from scores recorded before it was measured
+    #[serde(default)]
+    pub metrics: Option<Metrics>,
+}
+
+impl HighScore {
+    /// Play time, as precise as it was recorded
+    pub fn elapsed(&self) -> Duration {
+        match self.millis {
+            Some(millis) => Duration::from_millis(millis),
+            None => Duration::from_secs(self.seconds.max(0) as u64),
+        }
+    }
+}
+
+/// Best times of a board, best first
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    pub board: ScoreBoard,
+    pub scores: Vec<HighScore>,
+}
+
+/// Best times of all the boards which were won at least once
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    tables: Vec<HighScoreTable>,
+}
+
+impl HighScores {
+    /// Number of best times kept for each board
+    pub const MAX_SCORES: usize = 10;
+
+    /// Add a won game to the best times of its board. Assisted games always rank below unassisted ones. Returns the
+    /// rank of the game (`0` being the best time), or `None` if it's not one of the best times.
+    pub fn record(&mut self, board: ScoreBoard, score: HighScore) -> Option<usize> {
+        let index = match self.tables.binary_search_by(|table| table.board.cmp(&board)) {
+            Ok(index) => index,
+            Err(index) => {
+                self.tables.insert(index, HighScoreTable { board, scores: vec![] });
+                index
+            },
+        };
+
+        let scores = &mut self.tables[index].scores;
+        let key = |score: &HighScore| (score.assisted, score.elapsed());
+
+        // Ties go to the earlier game
+        let rank = scores.iter().position(|other| key(&score) < key(other)).unwrap_or(scores.len());
+        if rank >= Self::MAX_SCORES {
+            return None;
+        }
+
+        scores.insert(rank, score);
+        scores.truncate(Self::MAX_SCORES);
+
+        Some(rank)
+    }
+
+    /// Best times of a board, best first
+    pub fn scores(&self, board: ScoreBoard) -> &[HighScore] {
+        self.tables
+            .iter()
+            .find(|table| table.board == board)
+            .map(|table| table.scores.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Best times of all boards
+    pub fn tables(&self) -> &[HighScoreTable] {
+        &self.tables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(seconds: i32, assisted: bool) -> HighScore {
+        HighScore {
+            seconds,
+            millis: Some(seconds as u64 * 1000),
+            date: "2022-10-17 12:00".to_string(),
+            seed: seconds as u64,
+            assisted,
+            metrics: None,
+        }
+    }
+
+    #[test]
+    fn best_times() {
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+        let hard = ScoreBoard::from(&GameDifficulty::HARD);
+        let mut high_scores = HighScores::default();
+        assert!(high_scores.scores(easy).is_empty());
+
+        assert_eq!(high_scores.record(easy, score(30, false)), Some(0));
+        assert_eq!(high_scores.record(easy, score(20, false)), Some(0));
+        assert_eq!(high_scores.record(easy, score(25, false)), Some(1));
+        assert_eq!(high_scores.record(hard, score(300, false)), Some(0));
+
+        // Assisted games rank below unassisted ones, and ties go to the earlier game
+        assert_eq!(high_scores.record(easy, score(5, true)), Some(3));
+        assert_eq!(high_scores.record(easy, score(25, false)), Some(2));
+
+        let seconds: Vec<i32> = high_scores.scores(easy).iter().map(|score| score.seconds).collect();
+        assert_eq!(seconds, vec![20, 25, 25, 30, 5]);
+        assert_eq!(high_scores.scores(hard).len(), 1);
+
+        // Only the best times are kept
+        for seconds in 40..50 {
+            high_scores.record(easy, score(seconds, false));
+        }
+        assert_eq!(high_scores.scores(easy).len(), HighScores::MAX_SCORES);
+        assert_eq!(high_scores.record(easy, score(60, false)), None);
+        assert_eq!(high_scores.scores(easy).last().unwrap().seconds, 45);
+
+        // Boards are kept in order
+        let boards: Vec<ScoreBoard> = high_scores.tables().iter().map(|table| table.board).collect();
+        assert_eq!(boards, vec![easy, hard]);
+    }
+
+    #[test]
+    fn no_guess_boards() {
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+        let easy_no_guess = ScoreBoard { no_guess: true, ..easy };
+        let mut high_scores = HighScores::default();
+
+        // Games without guessing are ranked apart from the ordinary games on the same board
+        assert_eq!(high_scores.record(easy, score(30, false)), Some(0));
+        assert_eq!(high_scores.record(easy_no_guess, score(40, false)), Some(0));
+        assert_eq!(high_scores.scores(easy).len(), 1);
+        assert_eq!(high_scores.scores(easy_no_guess).len(), 1);
+
+        // Boards saved before the setting was kept are ordinary ones
+        let json = serde_json::to_string(&high_scores).unwrap().replace(",\"no_guess\":false", "");
+        let loaded: HighScores = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, high_scores);
+    }
+
+    #[test]
+    fn sub_second_times() {
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+        let mut high_scores = HighScores::default();
+
+        // Times within the same second are still ranked
+        let precise = |millis: u64| HighScore { seconds: (millis / 1000) as i32, millis: Some(millis), ..score(0, false) };
+        assert_eq!(high_scores.record(easy, precise(9_800)), Some(0));
+        assert_eq!(high_scores.record(easy, precise(9_200)), Some(0));
+        assert_eq!(high_scores.record(easy, precise(850)), Some(0));
+
+        // Scores recorded before the play time was counted in milliseconds
+        let json = serde_json::to_string(&score(9, false)).unwrap().replace("\"millis\":9000", "\"millis\":null");
+        let old_score: HighScore = serde_json::from_str(&json).unwrap();
+        assert_eq!(old_score.elapsed(), Duration::from_secs(9));
+        assert_eq!(high_scores.record(easy, old_score), Some(1));
+
+        let times: Vec<Duration> = high_scores.scores(easy).iter().map(HighScore::elapsed).collect();
+        assert_eq!(times, [850, 9_000, 9_200, 9_800].map(Duration::from_millis));
+    }
+
+    #[test]
+    fn board_names() {
+        assert_eq!(ScoreBoard::from(&GameDifficulty::MEDIUM).to_string(), "Medium");
+        assert_eq!(ScoreBoard { width: 20, height: 10, mines: 30, no_guess: false }.to_string(), "Custom 20x10, 30 mines");
+        assert_eq!(ScoreBoard { no_guess: true, ..ScoreBoard::from(&GameDifficulty::HARD) }.to_string(), "Hard, no guessing");
+        assert_eq!(ScoreBoard::of_minefield(&Minefield::new(30, 16).with_mines_seeded(99, 1)).difficulty(), Some(GameDifficulty::Hard));
+    }
+}
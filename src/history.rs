@@ -0,0 +1,213 @@
+use crate::minefield;
+
+use minefield::{Minefield, SpotState, StepResult};
+
+/// What it takes to go from one side of a move to the other
+#[derive(Clone, Debug)]
+enum Change {
+    /// The spots whose state changed, by field index, with their state on the other side of the move
+    Spots(Vec<(usize, SpotState)>),
+
+    /// The whole minefield on the other side of the move, for the move which placed the pending mines
+    Minefield(Box<Minefield>),
+}
+
+/// A minefield, along with the history of the moves made on it, which can be undone and redone
+#[derive(Clone, Debug)]
+pub struct History {
+    minefield: Minefield,
+
+    /// Changes undoing each of the moves which can be undone, oldest first
+    undo: Vec<Change>,
+
+    /// Changes redoing each of the moves which can be redone, most recently undone last
+    redo: Vec<Change>,
+}
+
+impl History {
+    /// Start the history of a minefield
+    pub fn new(minefield: Minefield) -> Self {
+        Self { minefield, undo: vec![], redo: vec![] }
+    }
+
+    /// The current state of the minefield
+    pub fn minefield(&self) -> &Minefield {
+        &self.minefield
+    }
+
+    /// Step on a given spot of the field (see `Minefield::step()`), recording the move
+    pub fn step(&mut self, x: u16, y: u16) -> StepResult {
+        self.record(|minefield| minefield.step(x, y))
+    }
+
+    /// Try to reveal the neighbors of a given spot (see `Minefield::try_resolve_step()`), recording the move
+    pub fn try_resolve_step(&mut self, x: u16, y: u16) -> StepResult {
+        self.record(|minefield| minefield.try_resolve_step(x, y))
+    }
+
+    /// Toggle the flag on a given spot (see `Minefield::toggle_flag()`), recording the move
+    pub fn toggle_flag(&mut self, x: u16, y: u16) -> i32 {
+        self.record(|minefield| minefield.toggle_flag(x, y))
+    }
+
+    /// Check if there is a move which can be undone
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Check if there is an undone move which can be redone
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Number of moves which can be undone. Moves which changed nothing are not counted.
+    pub fn moves(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Undo the last move. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        if let Some(change) = self.undo.pop() {
+            let change = self.apply(change);
+            self.redo.push(change);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Redo the last undone move. Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if let Some(change) = self.redo.pop() {
+            let change = self.apply(change);
+            self.undo.push(change);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Make a move, and remember how to undo it if it changed the minefield. Making a new move discards the moves which
+    /// could have been redone.
+    fn record<T>(&mut self, make_move: impl FnOnce(&mut Minefield) -> T) -> T {
+        // Only the spot states change, except on the first step, which places the pending mines
+        let previous_minefield = self.minefield.has_pending_mines().then(|| self.minefield.clone());
+        let previous_states = self.minefield.spot_states();
+
+        let result = make_move(&mut self.minefield);
+
+        let change = match previous_minefield {
+            Some(previous) if !self.minefield.has_pending_mines() => Change::Minefield(Box::new(previous)),
+            _ => Change::Spots(
+                previous_states
+                    .into_iter()
+                    .zip(self.minefield.spot_states())
+                    .enumerate()
+                    .filter(|(_, (previous, current))| previous != current)
+                    .map(|(index, (previous, _))| (index, previous))
+                    .collect()
+            ),
+        };
+
+        if !matches!(&change, Change::Spots(spots) if spots.is_empty()) {
+            self.undo.push(change);
+            self.redo.clear();
+        }
+
+        result
+    }
+
+    /// Go to the other side of a move, and return the change going back
+    fn apply(&mut self, change: Change) -> Change {
+        match change {
+            Change::Spots(spots) => Change::Spots(
+                spots
+                    .into_iter()
+                    .map(|(index, state)| (index, self.minefield.replace_spot_state(index, state)))
+                    .collect()
+            ),
+            Change::Minefield(minefield) => Change::Minefield(Box::new(std::mem::replace(&mut self.minefield, *minefield))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minefield::FirstStepPolicy;
+
+    #[test]
+    fn undo_redo() {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        let mut history = History::new(minefield);
+        assert!(!history.can_undo());
+        assert!(!history.undo());
+
+        // Invalid moves are not recorded
+        assert_eq!(history.step(5, 5), StepResult::Invalid);
+        assert_eq!(history.toggle_flag(5, 5), 0);
+        assert!(!history.can_undo());
+
+        assert_eq!(history.step(1, 2), StepResult::Phew);
+        assert_eq!(history.toggle_flag(2, 0), 1);
+        assert_eq!(history.step(0, 3), StepResult::Boom);
+        assert!(history.minefield().is_exploded());
+
+        // Undo the explosion
+        assert!(history.undo());
+        assert!(!history.minefield().is_exploded());
+        assert_eq!(history.minefield().spot(0, 3).unwrap().state(), SpotState::Hidden);
+        assert_eq!(history.minefield().spot(2, 0).unwrap().state(), SpotState::Flagged);
+
+        // Undo everything
+        assert!(history.undo());
+        assert!(history.undo());
+        assert!(!history.can_undo());
+        assert_eq!(history.minefield().spot(1, 2).unwrap().state(), SpotState::Hidden);
+
+        // Redo the first two moves
+        assert!(history.redo());
+        assert!(history.redo());
+        assert_eq!(history.minefield().spot(1, 2).unwrap().state(), SpotState::Revealed);
+        assert_eq!(history.minefield().spot(2, 0).unwrap().state(), SpotState::Flagged);
+        assert!(history.can_redo());
+
+        // A new move discards the moves which could have been redone
+        assert_eq!(history.step(0, 0), StepResult::Phew);
+        assert!(!history.can_redo());
+        assert!(!history.redo());
+
+        // Moves which change nothing are not recorded
+        let moves = history.moves();
+        assert_eq!(history.step(0, 0), StepResult::Phew);
+        assert_eq!(history.toggle_flag(0, 0), 0);
+        assert_eq!(history.moves(), moves);
+    }
+
+    #[test]
+    fn undo_first_step() {
+        let minefield = Minefield::new(10, 10).with_mines_deferred(10, 3, FirstStepPolicy::SafeSpot);
+        let mut history = History::new(minefield);
+
+        assert_eq!(history.step(4, 4), StepResult::Phew);
+        assert_eq!(history.toggle_flag(0, 0), 1);
+        assert!(matches!(&history.undo[..], [Change::Minefield(_), Change::Spots(spots)] if spots.len() == 1));
+
+        // The mines are placed again on the next first step
+        assert!(history.undo());
+        assert!(history.undo());
+        assert!(history.minefield().has_pending_mines());
+
+        // Redoing brings back the same mines
+        assert!(history.redo());
+        assert!(history.redo());
+        assert!(!history.minefield().has_pending_mines());
+        assert_eq!(history.minefield().spot(4, 4).unwrap().state(), SpotState::Revealed);
+        assert_eq!(history.minefield().spot(0, 0).unwrap().state(), SpotState::Flagged);
+    }
+}
@@ -0,0 +1,159 @@
+use eframe::egui::Key;
+use serde::{Serialize, Deserialize};
+
+/// Something which can be done with the keyboard
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum KeyAction {
+    /// Move the cursor over the board
+    Up,
+    Down,
+    Left,
+    Right,
+
+    /// Step on, flag or chord the spot under the cursor
+    Step,
+    Flag,
+    Chord,
+
+    /// Same as the refresh button
+    NewGame,
+
+    /// Same as the pause button
+    Pause,
+
+    /// Open or close the Settings and About windows
+    Settings,
+    About,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 11] = [
+        KeyAction::Up, KeyAction::Down, KeyAction::Left, KeyAction::Right,
+        KeyAction::Step, KeyAction::Flag, KeyAction::Chord,
+        KeyAction::NewGame, KeyAction::Pause, KeyAction::Settings, KeyAction::About,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyAction::Up => "Move up",
+            KeyAction::Down => "Move down",
+            KeyAction::Left => "Move left",
+            KeyAction::Right => "Move right",
+            KeyAction::Step => "Step",
+            KeyAction::Flag => "Flag",
+            KeyAction::Chord => "Chord",
+            KeyAction::NewGame => "New game",
+            KeyAction::Pause => "Pause",
+            KeyAction::Settings => "Settings",
+            KeyAction::About => "About",
+        }
+    }
+}
+
+/// The keys bound to each action. A key is bound to at most one action.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyAction, Vec<Key>)>,
+}
+
+impl KeyBindings {
+    /// Keys bound to an action
+    pub fn keys(&self, action: KeyAction) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound_action, _)| *bound_action == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The action a key is bound to, if any
+    pub fn action(&self, key: Key) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    /// Bind a key to an action, taking it away from the action it was bound to
+    pub fn bind(&mut self, action: KeyAction, key: Key) {
+        for (_, keys) in &mut self.bindings {
+            keys.retain(|&bound_key| bound_key != key);
+        }
+
+        match self.bindings.iter_mut().find(|(bound_action, _)| *bound_action == action) {
+            Some((_, keys)) => keys.push(key),
+            None => self.bindings.push((action, vec![key])),
+        }
+    }
+
+    /// Remove all the keys bound to an action
+    pub fn clear(&mut self, action: KeyAction) {
+        self.bindings.retain(|(bound_action, _)| *bound_action != action);
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (KeyAction::Up, vec![Key::ArrowUp, Key::W, Key::K]),
+                (KeyAction::Down, vec![Key::ArrowDown, Key::S, Key::J]),
+                (KeyAction::Left, vec![Key::ArrowLeft, Key::A, Key::H]),
+                (KeyAction::Right, vec![Key::ArrowRight, Key::D, Key::L]),
+                (KeyAction::Step, vec![Key::Space, Key::Enter]),
+                (KeyAction::Flag, vec![Key::F]),
+                (KeyAction::Chord, vec![Key::C]),
+                (KeyAction::NewGame, vec![Key::N]),
+                (KeyAction::Pause, vec![Key::P]),
+                (KeyAction::Settings, vec![Key::O]),
+                (KeyAction::About, vec![Key::F1]),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings() {
+        let key_bindings = KeyBindings::default();
+
+        // Every action has a key, and no key is bound twice
+        let mut keys = vec![];
+        for action in KeyAction::ALL {
+            assert!(!key_bindings.keys(action).is_empty(), "{:?} has no key", action);
+            for &key in key_bindings.keys(action) {
+                assert_eq!(key_bindings.action(key), Some(action));
+                keys.push(key);
+            }
+        }
+
+        let count = keys.len();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), count);
+    }
+
+    #[test]
+    fn rebind() {
+        let mut key_bindings = KeyBindings::default();
+
+        // A key moves from one action to the other
+        key_bindings.bind(KeyAction::Flag, Key::Space);
+        assert_eq!(key_bindings.keys(KeyAction::Flag), &[Key::F, Key::Space]);
+        assert_eq!(key_bindings.keys(KeyAction::Step), &[Key::Enter]);
+        assert_eq!(key_bindings.action(Key::Space), Some(KeyAction::Flag));
+
+        key_bindings.clear(KeyAction::Chord);
+        assert!(key_bindings.keys(KeyAction::Chord).is_empty());
+        assert_eq!(key_bindings.action(Key::C), None);
+        key_bindings.bind(KeyAction::Chord, Key::C);
+        assert_eq!(key_bindings.action(Key::C), Some(KeyAction::Chord));
+
+        // Bindings are persisted
+        let json = serde_json::to_string(&key_bindings).unwrap();
+        assert_eq!(serde_json::from_str::<KeyBindings>(&json).unwrap(), key_bindings);
+    }
+}
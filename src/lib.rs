@@ -1,2 +1,20 @@
+#[cfg(feature = "gui")]
 pub mod app;
-pub mod minefield;
\ No newline at end of file
+pub mod minefield;
+pub mod solver;
+pub mod history;
+pub mod save;
+pub mod board_formats;
+pub mod replay;
+pub mod rawvf;
+pub mod high_scores;
+pub mod stats;
+pub mod metrics;
+#[cfg(feature = "gui")]
+pub mod board;
+#[cfg(feature = "gui")]
+pub mod key_bindings;
+pub mod game;
+pub mod session;
+pub mod clock;
+pub mod cli;
\ No newline at end of file
@@ -1,11 +1,33 @@
 pub mod app;
 pub mod minefield;
+pub mod solver;
+pub mod history;
+pub mod save;
+pub mod board_formats;
+pub mod replay;
+pub mod rawvf;
+pub mod high_scores;
+pub mod stats;
+pub mod metrics;
+pub mod board;
+pub mod key_bindings;
+pub mod game;
+pub mod session;
+pub mod clock;
+pub mod cli;
 
-use std::env;
+use std::{env, process};
 use eframe::{NativeOptions, epaint::Vec2};
-use app::{GameDifficulty, MinesweepRsApp};
+use app::MinesweepRsApp;
+use game::GameDifficulty;
 
 fn main() {
+    // Headless mode, played with commands read from stdin
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--cli") {
+        process::exit(cli::main(&args));
+    }
+
     // DEBUG 
     env::set_var("RUST_BACKTRACE", "full");
     
@@ -15,19 +37,13 @@ fn main() {
         .with_line_number(true)
         .init();    
     
-    // FIXME: Solve auto resizing
-    let size_x = 38.0;
-    let size_y = 44.0;
+    // The window is resized to fit the board of the stored config once the app is started
+    let default_config = GameDifficulty::EASY;
 
     let options = NativeOptions {
-        initial_window_size: Some(
-            // Allocate the maximum native window size, corresponding to the HARD game difficulty
-            Vec2::new(
-                size_x * GameDifficulty::HARD.width as f32,
-                size_y * GameDifficulty::HARD.height as f32
-            )
-        ),
-        resizable: false,
+        initial_window_size: Some(MinesweepRsApp::window_size(default_config.width, default_config.height)),
+        min_window_size: Some(Vec2::new(240.0, 240.0)),
+        resizable: true,
         // FIXME: App crashes (on Fedora, with Wayland) when run with `options.run_and_return = true;` and in a `loop`
         run_and_return: true,
         follow_system_theme: false,
@@ -0,0 +1,71 @@
+use serde::{Serialize, Deserialize};
+
+/// Clicks made on the minefield during a game, including those which changed nothing
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Clicks {
+    /// Steps
+    pub left: u32,
+
+    /// Flags placed or removed
+    pub right: u32,
+
+    /// Chords on revealed numbers
+    pub chord: u32,
+}
+
+impl Clicks {
+    pub fn total(&self) -> u32 {
+        self.left + self.right + self.chord
+    }
+}
+
+/// Measures of how well a game was played, in the way the Minesweeper community compares games
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct Metrics {
+    /// Minimum number of clicks needed to clear the board (see `Minefield::bbbv()`)
+    pub bbbv: u32,
+    pub clicks: Clicks,
+}
+
+impl Metrics {
+    /// 3BV cleared per second
+    pub fn bbbv_per_second(&self, seconds: f64) -> Option<f64> {
+        (seconds > 0.0).then(|| self.bbbv as f64 / seconds)
+    }
+
+    /// Index Of Speed: `log(3BV) / log(seconds)`. It's only defined for games longer than a second.
+    pub fn ios(&self, seconds: f64) -> Option<f64> {
+        (seconds > 1.0 && self.bbbv > 0).then(|| (self.bbbv as f64).ln() / seconds.ln())
+    }
+
+    /// Rapport Qualité Prix: `seconds / 3BV/s`. Lower is better.
+    pub fn rqp(&self, seconds: f64) -> Option<f64> {
+        (self.bbbv > 0).then(|| seconds * seconds / self.bbbv as f64)
+    }
+
+    /// Ratio of the 3BV to the clicks made to clear the board
+    pub fn efficiency(&self) -> Option<f64> {
+        (self.clicks.total() > 0).then(|| self.bbbv as f64 / self.clicks.total() as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics() {
+        let metrics = Metrics { bbbv: 100, clicks: Clicks { left: 80, right: 30, chord: 15 } };
+        assert_eq!(metrics.clicks.total(), 125);
+        assert_eq!(metrics.bbbv_per_second(50.0), Some(2.0));
+        assert_eq!(metrics.rqp(50.0), Some(25.0));
+        assert_eq!(metrics.efficiency(), Some(0.8));
+        assert!((metrics.ios(10.0).unwrap() - 2.0).abs() < 1e-9);
+
+        // Degenerate games
+        let metrics = Metrics { bbbv: 1, clicks: Clicks::default() };
+        assert_eq!(metrics.bbbv_per_second(0.0), None);
+        assert_eq!(metrics.ios(1.0), None);
+        assert_eq!(metrics.efficiency(), None);
+    }
+}
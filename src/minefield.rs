@@ -1,7 +1,11 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Serialize, Deserialize};
+use std::{fmt, str::FromStr};
+use crate::solver;
 
 /// Type of spot in a minefield
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum SpotKind {
     /// This spot is a mine
     Mine,
@@ -11,7 +15,7 @@ pub enum SpotKind {
 }
 
 /// State of the spot in a minefield
-#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum SpotState {
     /// This spot has not been visited
     Hidden,
@@ -27,7 +31,7 @@ pub enum SpotState {
 }
 
 /// Spot struct describing the characteristics of the minefield at a particular position
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub struct Spot {
     kind: SpotKind,
     state: SpotState,
@@ -62,14 +66,61 @@ pub enum StepResult {
     Invalid
 }
 
+/// How the first step in a minefield is protected from hitting a mine
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum FirstStepPolicy {
+    /// Mines are placed up front, so the first step may be a `Boom`
+    Unprotected,
+
+    /// Mines are placed on the first step, and never on the stepped spot
+    #[default]
+    SafeSpot,
+
+    /// Mines are placed on the first step, and neither on the stepped spot nor on its neighbors, so that the first step
+    /// always flood reveals an area (unless the field is too crowded for that)
+    SafeNeighborhood,
+}
+
+/// Limits on the search for a minefield which can be cleared without guessing. The search is bounded by a number of
+/// attempts rather than by time, so that a seed gives the same minefield on every machine.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct NoGuessBudget {
+    /// Maximum number of candidate layouts to try
+    pub attempts: u32,
+}
+
+impl NoGuessBudget {
+    pub const DEFAULT: NoGuessBudget = NoGuessBudget { attempts: 1_000 };
+}
+
+impl Default for NoGuessBudget {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Mine placement waiting for the first step
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+struct PendingMines {
+    seed: u64,
+    policy: FirstStepPolicy,
+    no_guess: Option<NoGuessBudget>,
+}
+
 /// The characteristics of the minefield
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Minefield {
     field: Vec<Spot>,
 
     /// Number of mines in the field
     mines: i32,
 
+    /// How to place the mines on the first step, if their placement has been deferred
+    pending_mines: Option<PendingMines>,
+
+    /// The mines were placed such that the field can be cleared without guessing
+    no_guess: bool,
+
     /// Width of field grid
     width: i32,
 
@@ -78,6 +129,9 @@ pub struct Minefield {
 }
 
 impl Minefield {
+    /// Ratio of mines to spots above which no attempt is made to generate a field which can be cleared without guessing
+    const NO_GUESS_MAX_DENSITY: f32 = 0.25;
+
     /// Create an empty minefield grid (with all spots hidden), with the given width and height
     pub fn new(width: u16, height: u16) -> Self {
         // Enforce a minimum number of spots
@@ -94,44 +148,197 @@ impl Minefield {
         Minefield {
             field,
             mines: 0,
+            pending_mines: None,
+            no_guess: false,
             width,
             height,
         }
     }
 
     /// Build an existing minefield with the given number of mines randomly placed in it
-    pub fn with_mines(mut self, mines: u16) -> Self {
-        // Total number of spots in our field
-        let spot_count = self.width as usize * self.height as usize;
+    pub fn with_mines(self, mines: u16) -> Self {
+        self.with_mines_rng(mines, &mut rand::thread_rng())
+    }
+
+    /// Build an existing minefield with the given number of mines placed in it according to the given `seed`.
+    ///
+    /// The same seed, dimensions and mine count always produce the same layout, on every platform (including wasm32).
+    pub fn with_mines_seeded(self, mines: u16, seed: u64) -> Self {
+        self.with_mines_rng(mines, &mut ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// Build an existing minefield with the given number of mines placed in it using the given random number generator
+    pub fn with_mines_rng<R: Rng + ?Sized>(mut self, mines: u16, rng: &mut R) -> Self {
+        self.mines = self.clamp_mines(mines);
+        self.place_random_mines(rng, &[]);
 
-        // Limit the max number of mines to the number of available spots
-        let mines = if mines as usize <= spot_count { mines as i32 } else { spot_count as i32 };
+        self
+    }
+
+    /// Build an existing minefield with mines placed at the given coordinates. Coordinates outside the field, and
+    /// repeated coordinates, are ignored.
+    pub fn with_mines_at(mut self, mines: &[(u16, u16)]) -> Self {
+        for &(x, y) in mines {
+            if let Some(index) = self.spot_index(x as i32, y as i32) {
+                if self.field[index].kind != SpotKind::Mine {
+                    self.place_mine(index);
+                    self.mines += 1;
+                }
+            }
+        }
 
-        self.mines = mines;
+        self
+    }
 
-        // Add mines to minefield
+    /// Build an existing minefield with the mines of `other`, keeping its own spot states. Nothing changes if the two
+    /// minefields aren't the same size.
+    pub fn with_mines_of(mut self, other: &Minefield) -> Self {
+        if (self.width, self.height) == (other.width, other.height) {
+            for (spot, other_spot) in self.field.iter_mut().zip(&other.field) {
+                spot.kind = other_spot.kind;
+            }
+
+            self.mines = other.mines;
+            self.pending_mines = other.pending_mines;
+            self.no_guess = other.no_guess;
+        }
+
+        self
+    }
+
+    /// Build an existing minefield with the given number of mines, placed according to the given `seed` and first step
+    /// `policy`.
+    ///
+    /// Unless the policy is `FirstStepPolicy::Unprotected`, the mines are only placed when the field is first stepped
+    /// on, avoiding the spots protected by the policy.
+    pub fn with_mines_deferred(mut self, mines: u16, seed: u64, policy: FirstStepPolicy) -> Self {
+        if policy == FirstStepPolicy::Unprotected {
+            return self.with_mines_seeded(mines, seed);
+        }
+
+        self.mines = self.clamp_mines(mines);
+        self.pending_mines = Some(PendingMines { seed, policy, no_guess: None });
+
+        self
+    }
+
+    /// Build an existing minefield with the given number of mines, placed on the first step such that the field can be
+    /// cleared from there by logical deduction alone.
+    ///
+    /// The first step always reveals an area. Candidate layouts generated from the `seed` are tried until one can be
+    /// solved without guessing, within the given `budget`. If the budget runs out, or if the field is too crowded for
+    /// such a layout to be likely, an ordinary layout is used instead (see `is_no_guess()`).
+    pub fn with_mines_no_guess(mut self, mines: u16, seed: u64, budget: NoGuessBudget) -> Self {
+        self.mines = self.clamp_mines(mines);
+        self.pending_mines = Some(PendingMines {
+            seed,
+            policy: FirstStepPolicy::SafeNeighborhood,
+            no_guess: Some(budget),
+        });
+
+        self
+    }
+
+    /// Check if the placement of mines is still waiting for the first step
+    pub fn has_pending_mines(&self) -> bool {
+        self.pending_mines.is_some()
+    }
+
+    /// Check if the mines were placed such that the field can be cleared without guessing
+    pub fn is_no_guess(&self) -> bool {
+        self.no_guess
+    }
+
+    /// Limit the max number of mines to the number of available spots
+    fn clamp_mines(&self, mines: u16) -> i32 {
+        let spot_count = self.field.len();
+
+        if mines as usize <= spot_count { mines as i32 } else { spot_count as i32 }
+    }
+
+    /// Place the deferred mines (if any), protecting the spot at the given `index` according to the pending policy
+    fn place_pending_mines(&mut self, index: usize) {
+        if let Some(pending) = self.pending_mines.take() {
+            let mut excluded = vec![index];
+            if pending.policy == FirstStepPolicy::SafeNeighborhood {
+                excluded.extend(self.neighbor_indices(index));
+            }
+
+            // If the field is too crowded, fall back to protecting just the stepped spot, and then to no protection
+            let free_spots = self.field.len() as i32;
+            if free_spots - (excluded.len() as i32) < self.mines {
+                excluded.truncate(1);
+            }
+            if free_spots - (excluded.len() as i32) < self.mines {
+                excluded.clear();
+            }
+
+            if let Some(budget) = pending.no_guess {
+                if self.place_no_guess_mines(&mut ChaCha8Rng::seed_from_u64(pending.seed), index, &excluded, budget) {
+                    return;
+                }
+            }
 
+            // A fresh generator, so that the fallback layout doesn't depend on how many candidates were tried
+            self.place_random_mines(&mut ChaCha8Rng::seed_from_u64(pending.seed), &excluded);
+        }
+    }
+
+    /// Try to place the mines such that the field can be cleared without guessing after stepping on the spot at the
+    /// given `index`. Returns `false` (leaving the field untouched) if no such layout was found within the `budget`.
+    fn place_no_guess_mines(&mut self, rng: &mut ChaCha8Rng, index: usize, excluded: &[usize], budget: NoGuessBudget) -> bool {
+        // Past this density, solvable layouts are so rare that searching for one would just stall the game
+        if self.mines as f32 > Self::NO_GUESS_MAX_DENSITY * self.field.len() as f32 {
+            return false;
+        }
+
+        let (x, y) = self.spot_coords(index);
+
+        for _ in 0..budget.attempts {
+            // Candidates start out without any of the flags the player may have placed before the first step
+            let mut candidate = Minefield::new(self.width as u16, self.height as u16);
+            candidate.mines = self.mines;
+            candidate.place_random_mines(rng, excluded);
+
+            if solver::clears_without_guessing(&candidate, x as u16, y as u16) {
+                for (spot, candidate_spot) in self.field.iter().zip(candidate.field.iter_mut()) {
+                    candidate_spot.state = spot.state;
+                }
+
+                candidate.no_guess = true;
+                *self = candidate;
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    /// Randomly place `self.mines` mines in the field, avoiding the `excluded` field indices
+    fn place_random_mines<R: Rng + ?Sized>(&mut self, rng: &mut R, excluded: &[usize]) {
         // We could just start randomly picking indices in the field and hope we haven't picked them before, but if a
         // user desires a field full of mines, then waiting for the last mines to be placed might take a long time
         // (e.g. if the field is very large).
         // That's a problem for an immediate GUI.
         // So, instead, we'll use some memory in order to ensure that the user can step on a mine as soon as humanly
         // possible.
-        let mut spots_remaining: Vec<usize> = (0..spot_count).collect();
-        let mut rng = rand::thread_rng();
+        let mut spots_remaining: Vec<usize> = (0..self.field.len()).filter(|i| !excluded.contains(i)).collect();
 
         // Place mines
         for _ in 0..self.mines {
-            let index_rm = rng.gen_range(0..spots_remaining.len());
+            // Sample a `u32` rather than a `usize`, so that the sequence of picked spots does not depend on the
+            // pointer width of the target (wasm32 has 32 bit pointers)
+            let index_rm = rng.gen_range(0..spots_remaining.len() as u32) as usize;
             self.place_mine(spots_remaining.swap_remove(index_rm));
         }
-
-        self
     }
 
     /// Step on a given spot of the field. Coordinates [x=0, y=0] represent the top-left point of the field grid
     pub fn step(&mut self, x: u16, y: u16) -> StepResult {
         if let Some(index) = self.spot_index(x as i32, y as i32) {
+            self.place_pending_mines(index);
+
             match self.field[index].kind {
                 SpotKind::Mine => {
                     // Stepped on a mine
@@ -217,6 +424,16 @@ impl Minefield {
         true
     }
 
+    /// Check if a mine has exploded
+    pub fn is_exploded(&self) -> bool {
+        self.field.iter().any(|spot| spot.state == SpotState::Exploded)
+    }
+
+    /// The number of flagged spots in the minefield
+    pub fn flags(&self) -> u16 {
+        self.field.iter().filter(|spot| spot.state == SpotState::Flagged).count() as u16
+    }
+
     /// Set a flag on a hidden spot (return `1`), or clear the flag if the spot had one (return `-1`), or do nothing if
     /// the spot cannot be flagged (return `0`)
     pub fn toggle_flag(&mut self, x: u16, y: u16) -> i32 {
@@ -261,6 +478,127 @@ impl Minefield {
         }
     }
 
+    /// The states of all the spots, in field index order
+    pub(crate) fn spot_states(&self) -> Vec<SpotState> {
+        self.field.iter().map(|spot| spot.state).collect()
+    }
+
+    /// Change the state of the spot at the given field index, and return its previous state. Meant for undoing and
+    /// redoing moves, so none of the rules of the game are applied.
+    pub(crate) fn replace_spot_state(&mut self, index: usize, state: SpotState) -> SpotState {
+        std::mem::replace(&mut self.field[index].state, state)
+    }
+
+    /// Check that the minefield is internally consistent: the field matches the dimensions, the mine count matches the
+    /// mines in the field (once placed), and each empty spot knows its number of neighboring mines. A minefield which
+    /// was deserialized from untrusted data must pass this check before being used.
+    pub fn is_well_formed(&self) -> bool {
+        if self.width < 3 || self.height < 1 || self.field.len() != self.width as usize * self.height as usize {
+            return false;
+        }
+
+        let mine_count = self.field.iter().filter(|spot| spot.kind == SpotKind::Mine).count() as i32;
+        let expected_mine_count = if self.pending_mines.is_some() { 0 } else { self.mines };
+        if mine_count != expected_mine_count || self.mines as usize > self.field.len() {
+            return false;
+        }
+
+        self.field.iter().enumerate().all(|(index, spot)| {
+            match spot.kind {
+                SpotKind::Mine => spot.state != SpotState::Revealed,
+                SpotKind::Empty(n) => {
+                    let neighbor_mines = self
+                        .neighbor_indices(index)
+                        .filter(|i| self.field[*i].kind == SpotKind::Mine)
+                        .count() as i32;
+
+                    n == neighbor_mines && spot.state != SpotState::Exploded
+                },
+            }
+        })
+    }
+
+    /// The 3BV ("Bechtel's Board Benchmark Value") of the minefield: the minimum number of clicks needed to clear it,
+    /// which is the number of openings (connected areas of spots without neighboring mines, which are revealed with a
+    /// single click) plus the number of spots with neighboring mines which aren't on the edge of any opening.
+    /// Returns `None` if the mines have not been placed yet.
+    pub fn bbbv(&self) -> Option<u32> {
+        if self.pending_mines.is_some() {
+            return None;
+        }
+
+        let is_opening = |index: usize| self.field[index].kind == SpotKind::Empty(0);
+        let mut visited = vec![false; self.field.len()];
+        let mut bbbv = 0;
+
+        // Openings
+        for index in 0..self.field.len() {
+            if !is_opening(index) || visited[index] {
+                continue;
+            }
+
+            bbbv += 1;
+            visited[index] = true;
+            let mut to_visit = vec![index];
+            while let Some(index) = to_visit.pop() {
+                for neighbor_index in self.neighbor_indices(index) {
+                    if is_opening(neighbor_index) && !visited[neighbor_index] {
+                        visited[neighbor_index] = true;
+                        to_visit.push(neighbor_index);
+                    }
+                }
+            }
+        }
+
+        // Isolated numbers
+        for (index, spot) in self.field.iter().enumerate() {
+            if let SpotKind::Empty(n) = spot.kind {
+                if n > 0 && !self.neighbor_indices(index).any(is_opening) {
+                    bbbv += 1;
+                }
+            }
+        }
+
+        Some(bbbv)
+    }
+
+    /// Get an iterator over the coordinates of the spots neighboring the given coordinates
+    pub fn neighbors(&self, x: u16, y: u16) -> impl Iterator<Item = (u16, u16)> + '_ {
+        self.spot_index(x as i32, y as i32)
+            .into_iter()
+            .flat_map(|index| self.neighbor_indices(index))
+            .map(|index| {
+                let (x, y) = self.spot_coords(index);
+                (x as u16, y as u16)
+            })
+    }
+
+    /// The minefield as the player sees it (see `Spot::view_symbol()`), with the coordinates of the spots around it
+    pub fn state_view(&self) -> String {
+        let label_width = (self.height - 1).to_string().len();
+
+        // Every column is as wide as the widest x label, so that the spots stay under their labels
+        let column_width = (self.width - 1).to_string().len();
+
+        // X axis
+        let mut view = " ".repeat(label_width + 2);
+        for x in 0..self.width {
+            view.push_str(&format!(" {:>width$}", x, width = column_width));
+        }
+        view.push('\n');
+
+        for (y, row) in self.field.chunks(self.width as usize).enumerate() {
+            // Y axis
+            view.push_str(&format!("{:>width$} [", y, width = label_width));
+            for spot in row {
+                view.push_str(&format!(" {:>width$}", spot.view_symbol(), width = column_width));
+            }
+            view.push_str(" ]\n");
+        }
+
+        view
+    }
+
     /// Flood reveal the neighboring empty and hidden spots of the spot corresponding to the given `index`
     fn flood_neighbors_reveal(&mut self, index: usize) {
         let mut neighbors_to_visit = vec![index];
@@ -364,6 +702,166 @@ impl Minefield {
     }
 }
 
+/// Plain-ASCII notation of a minefield, with both its layout and its play state. Each row of the field is written on
+/// its own line, and each spot is written as one of:
+///
+/// ```text
+/// .  hidden empty spot       *  hidden mine
+/// f  flagged empty spot      F  flagged mine
+/// 0-8  revealed empty spot   X  exploded mine
+/// ```
+///
+/// The spots of a row are separated by spaces when formatting, and whitespace between them is ignored when parsing.
+/// Empty lines and lines starting with `#` are ignored as well, so that boards can be annotated. For example:
+///
+/// ```text
+/// # Flag the mine in the top-right corner
+/// 0 1 F
+/// 0 1 1
+/// 1 1 .
+/// * . .
+/// ```
+///
+/// Mines which have not been placed yet (see `Minefield::with_mines_deferred()`) are not part of the notation.
+impl fmt::Display for Minefield {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.field.chunks(self.width as usize) {
+            let symbols: Vec<String> = row.iter().map(|spot| spot.symbol().to_string()).collect();
+            writeln!(f, "{}", symbols.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Minefield {
+    type Err = ParseMinefieldError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rows: Vec<Vec<char>> = s
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect())
+            .collect();
+
+        let height = rows.len();
+        let width = rows.first().map(Vec::len).unwrap_or(0);
+        if width < 3 || height < 1 || width > u16::MAX as usize || height > u16::MAX as usize {
+            return Err(ParseMinefieldError::Size { width, height });
+        }
+
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            return Err(ParseMinefieldError::UnevenRow { y: y as u16 });
+        }
+
+        let mut minefield = Minefield::new(width as u16, height as u16);
+
+        // Place the mines first, so that the numbers of the revealed spots can be checked
+        for (y, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.iter().enumerate() {
+                let (is_mine, state) = Spot::parse_symbol(*symbol).ok_or(ParseMinefieldError::InvalidSpot {
+                    x: x as u16,
+                    y: y as u16,
+                    symbol: *symbol,
+                })?;
+
+                let index = y * width + x;
+                if is_mine {
+                    minefield.place_mine(index);
+                    minefield.mines += 1;
+                }
+                minefield.field[index].state = state;
+            }
+        }
+
+        for (y, row) in rows.iter().enumerate() {
+            for (x, symbol) in row.iter().enumerate() {
+                if let Some(n) = symbol.to_digit(10) {
+                    if minefield.field[y * width + x].kind != SpotKind::Empty(n as i32) {
+                        return Err(ParseMinefieldError::WrongNumber { x: x as u16, y: y as u16 });
+                    }
+                }
+            }
+        }
+
+        Ok(minefield)
+    }
+}
+
+impl Spot {
+    /// The symbol of the spot in the minefield notation
+    fn symbol(&self) -> char {
+        match (self.kind, self.state) {
+            (SpotKind::Empty(_), SpotState::Hidden) => '.',
+            (SpotKind::Mine, SpotState::Hidden) => '*',
+            (SpotKind::Empty(_), SpotState::Flagged) => 'f',
+            (SpotKind::Mine, SpotState::Flagged) => 'F',
+            (SpotKind::Empty(n), _) => char::from_digit(n as u32, 10).unwrap_or('?'),
+            (SpotKind::Mine, _) => 'X',
+        }
+    }
+
+    /// The symbol of the spot as the player sees it: hidden spots are `•`, flags `⚐`, exploded mines `💥`, and revealed
+    /// spots show their number of neighboring mines (blank if there are none)
+    pub fn view_symbol(&self) -> char {
+        match (self.state, self.kind) {
+            (SpotState::Hidden, _) => '•',
+            (SpotState::Flagged, _) => '⚐',
+            (SpotState::Exploded, _) => '💥',
+            (SpotState::Revealed, SpotKind::Mine) => '☢',
+            (SpotState::Revealed, SpotKind::Empty(0)) => ' ',
+            (SpotState::Revealed, SpotKind::Empty(n)) => char::from_digit(n as u32, 10).unwrap_or('?'),
+        }
+    }
+
+    /// Whether a symbol of the minefield notation stands for a mine, and the state of the spot
+    fn parse_symbol(symbol: char) -> Option<(bool, SpotState)> {
+        match symbol {
+            '.' => Some((false, SpotState::Hidden)),
+            '*' => Some((true, SpotState::Hidden)),
+            'f' => Some((false, SpotState::Flagged)),
+            'F' => Some((true, SpotState::Flagged)),
+            '0'..='8' => Some((false, SpotState::Revealed)),
+            'X' => Some((true, SpotState::Exploded)),
+            _ => None,
+        }
+    }
+}
+
+/// Reasons for which the notation of a minefield can't be parsed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ParseMinefieldError {
+    /// The field is smaller than 3 spots wide and 1 spot high, or too large
+    Size { width: usize, height: usize },
+
+    /// The row doesn't have as many spots as the first one
+    UnevenRow { y: u16 },
+
+    /// The symbol doesn't stand for any spot
+    InvalidSpot { x: u16, y: u16, symbol: char },
+
+    /// The number of the revealed spot doesn't match its neighboring mines
+    WrongNumber { x: u16, y: u16 },
+}
+
+impl fmt::Display for ParseMinefieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseMinefieldError::Size { width, height } => {
+                write!(f, "Unsupported minefield size {}x{} (at least 3x1 spots are needed)", width, height)
+            },
+            ParseMinefieldError::UnevenRow { y } => write!(f, "Row {} doesn't have as many spots as the first row", y),
+            ParseMinefieldError::InvalidSpot { x, y, symbol } => write!(f, "Invalid spot '{}' at {}, {}", symbol, x, y),
+            ParseMinefieldError::WrongNumber { x, y } => {
+                write!(f, "The number at {}, {} doesn't match the neighboring mines", x, y)
+            },
+        }
+    }
+}
+
+impl std::error::Error for ParseMinefieldError {}
+
  #[cfg(test)]
  mod tests {
      use super::*;
@@ -619,6 +1117,229 @@ impl Minefield {
         assert_eq!(minefield.field[index].state, SpotState::Hidden);
      }
 
+     #[test]
+     fn seeded_mines() {
+        let width = 10;
+        let height = 10;
+        let mines = 10;
+
+        // The same seed always yields the same layout
+        let minefield_a = Minefield::new(width, height).with_mines_seeded(mines, 42);
+        let minefield_b = Minefield::new(width, height).with_mines_seeded(mines, 42);
+        assert_eq!(minefield_a.mines(), mines);
+        for (spot_a, spot_b) in minefield_a.field.iter().zip(minefield_b.field.iter()) {
+            assert_eq!(spot_a.kind, spot_b.kind);
+        }
+
+        // A different seed yields a different layout
+        let minefield_c = Minefield::new(width, height).with_mines_seeded(mines, 43);
+        assert!(minefield_a.field.iter().zip(minefield_c.field.iter()).any(|(a, c)| a.kind != c.kind));
+
+        // The layout for a given seed is stable across platforms and releases
+        let mine_indices: Vec<usize> = minefield_a.field
+            .iter()
+            .enumerate()
+            .filter(|(_, spot)| spot.kind == SpotKind::Mine)
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(mine_indices, vec![14, 22, 26, 32, 40, 58, 67, 74, 92, 96]);
+     }
+
+     #[test]
+     fn deferred_mines() {
+        let width = 10;
+        let height = 10;
+        let mines = 20;
+
+        // Mines are not placed before the first step
+        let mut minefield = Minefield::new(width, height).with_mines_deferred(mines, 7, FirstStepPolicy::SafeNeighborhood);
+        assert!(minefield.has_pending_mines());
+        assert_eq!(minefield.mines(), mines);
+        assert!(minefield.field.iter().all(|spot| spot.kind == SpotKind::Empty(0)));
+
+        // The first step is safe, and so are its neighbors
+        let step_x = 4;
+        let step_y = 5;
+        assert_eq!(minefield.step(step_x, step_y), StepResult::Phew);
+        assert!(!minefield.has_pending_mines());
+        assert_eq!(minefield.field.iter().filter(|spot| spot.kind == SpotKind::Mine).count(), mines as usize);
+
+        let step_index = minefield.spot_index(step_x as i32, step_y as i32).unwrap();
+        assert_eq!(minefield.field[step_index].kind, SpotKind::Empty(0));
+        for neighbor_index in minefield.neighbor_indices(step_index) {
+            assert_eq!(minefield.field[neighbor_index].state, SpotState::Revealed);
+        }
+
+        // With the `SafeSpot` policy, only the stepped spot is protected
+        let mut minefield = Minefield::new(3, 3).with_mines_deferred(8, 7, FirstStepPolicy::SafeSpot);
+        assert_eq!(minefield.step(1, 1), StepResult::Phew);
+        assert_eq!(minefield.field[4].kind, SpotKind::Empty(8));
+
+        // A field too crowded to protect the whole neighborhood still protects the stepped spot
+        let mut minefield = Minefield::new(3, 3).with_mines_deferred(8, 7, FirstStepPolicy::SafeNeighborhood);
+        assert_eq!(minefield.step(0, 0), StepResult::Phew);
+        assert_eq!(minefield.field[0].kind, SpotKind::Empty(3));
+
+        // Unprotected fields have their mines placed up front
+        let minefield = Minefield::new(width, height).with_mines_deferred(mines, 7, FirstStepPolicy::Unprotected);
+        assert!(!minefield.has_pending_mines());
+        assert_eq!(minefield.field.iter().filter(|spot| spot.kind == SpotKind::Mine).count(), mines as usize);
+     }
+
+     #[test]
+     fn no_guess_mines() {
+        let width = 9;
+        let height = 9;
+        let mines = 10;
+        let step_x = 4;
+        let step_y = 4;
+
+        // The generated field can be cleared by logical deduction alone
+        let minefield = Minefield::new(width, height).with_mines_no_guess(mines, 3, NoGuessBudget::default());
+        assert!(minefield.has_pending_mines());
+
+        let mut stepped = minefield.clone();
+        assert_eq!(stepped.step(step_x, step_y), StepResult::Phew);
+        assert!(stepped.is_no_guess());
+        assert_eq!(stepped.field.iter().filter(|spot| spot.kind == SpotKind::Mine).count(), mines as usize);
+
+        let mut replayed = Minefield::new(width, height);
+        replayed.mines = mines as i32;
+        for (index, spot) in stepped.field.iter().enumerate() {
+            if spot.kind == SpotKind::Mine {
+                replayed.place_mine(index);
+            }
+        }
+        assert!(solver::clears_without_guessing(&replayed, step_x, step_y));
+
+        // The same seed and first step always yield the same field
+        let mut stepped_again = minefield;
+        stepped_again.step(step_x, step_y);
+        for (spot_a, spot_b) in stepped.field.iter().zip(stepped_again.field.iter()) {
+            assert_eq!(spot_a.kind, spot_b.kind);
+        }
+
+        // A crowded field falls back to an ordinary layout, which still protects the first step
+        let mut crowded = Minefield::new(width, height).with_mines_no_guess(40, 3, NoGuessBudget::default());
+        assert_eq!(crowded.step(step_x, step_y), StepResult::Phew);
+        assert!(!crowded.is_no_guess());
+        assert_eq!(crowded.field.iter().filter(|spot| spot.kind == SpotKind::Mine).count(), 40);
+     }
+
+     #[test]
+     fn no_guess_fallback() {
+        // When the budget runs out, the ordinary layout of the seed is used, however many candidates were tried
+        let mut ordinary = Minefield::new(30, 16).with_mines_deferred(118, 5, FirstStepPolicy::SafeNeighborhood);
+        ordinary.step(15, 8);
+
+        for attempts in [1, 2, 3] {
+            let mut minefield = Minefield::new(30, 16).with_mines_no_guess(118, 5, NoGuessBudget { attempts });
+            minefield.step(15, 8);
+            assert!(!minefield.is_no_guess());
+            assert_eq!(minefield.to_string(), ordinary.to_string());
+        }
+     }
+
+     #[test]
+     fn bbbv() {
+        // Two openings, and every number is on the edge of one of them
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        assert_eq!(minefield.bbbv(), Some(2));
+
+        // No openings, so each number must be clicked
+        //     0 1 2 3
+        // 0 [ ☢ 1 1 ☢ ]
+        let minefield = Minefield::new(4, 1).with_mines_at(&[(0, 0), (3, 0)]);
+        assert_eq!(minefield.bbbv(), Some(2));
+
+        // One opening, plus an isolated number
+        //     0 1 2 3 4 5
+        // 0 [     1 ☢ 2 ☢ ]
+        let minefield = Minefield::new(6, 1).with_mines_at(&[(3, 0), (5, 0)]);
+        assert_eq!(minefield.bbbv(), Some(2));
+
+        // The 3BV is only known once the mines are placed
+        let mut minefield = Minefield::new(9, 9).with_mines_deferred(10, 42, FirstStepPolicy::SafeSpot);
+        assert_eq!(minefield.bbbv(), None);
+        minefield.step(4, 4);
+        assert!(minefield.bbbv().unwrap() > 0);
+     }
+
+     #[test]
+     fn notation() {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let notation = "
+            # Flag the mine in the top-right corner
+            0 1 F
+            0 1 1
+
+            1 1 .
+            * . .
+        ";
+        let minefield: Minefield = notation.parse().unwrap();
+        assert_eq!(minefield.width(), 3);
+        assert_eq!(minefield.height(), 4);
+        assert_eq!(minefield.mines(), 2);
+        assert!(minefield.is_well_formed());
+        assert_eq!(minefield.spot(2, 0).unwrap().kind(), SpotKind::Mine);
+        assert_eq!(minefield.spot(2, 0).unwrap().state(), SpotState::Flagged);
+        assert_eq!(minefield.spot(0, 3).unwrap().kind(), SpotKind::Mine);
+        assert_eq!(minefield.spot(0, 3).unwrap().state(), SpotState::Hidden);
+        assert_eq!(minefield.spot(1, 2).unwrap().state(), SpotState::Revealed);
+        assert_eq!(minefield.spot(2, 2).unwrap().state(), SpotState::Hidden);
+        assert_eq!(minefield.to_string(), "0 1 F\n0 1 1\n1 1 .\n* . .\n");
+
+        // Whitespace between spots is optional
+        let compact: Minefield = "01F\n011\n11.\n*..".parse().unwrap();
+        assert_eq!(compact.to_string(), minefield.to_string());
+
+        // The play state of a game round-trips through the notation
+        let mut played = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        played.toggle_flag(1, 3);
+        played.step(2, 3);
+        played.step(0, 3);
+        assert_eq!(played.to_string(), ". . *\n. 1 1\n. 1 0\nX f 0\n");
+        let parsed: Minefield = played.to_string().parse().unwrap();
+        assert_eq!(parsed.to_string(), played.to_string());
+        assert!(parsed.is_exploded());
+        assert_eq!(parsed.flags(), 1);
+
+        // Invalid notations
+        assert_eq!("".parse::<Minefield>().unwrap_err(), ParseMinefieldError::Size { width: 0, height: 0 });
+        assert_eq!(". .\n. .".parse::<Minefield>().unwrap_err(), ParseMinefieldError::Size { width: 2, height: 2 });
+        assert_eq!("...\n..".parse::<Minefield>().unwrap_err(), ParseMinefieldError::UnevenRow { y: 1 });
+        assert_eq!(
+            "...\n.?.".parse::<Minefield>().unwrap_err(),
+            ParseMinefieldError::InvalidSpot { x: 1, y: 1, symbol: '?' }
+        );
+        assert_eq!("*1.\n.2.".parse::<Minefield>().unwrap_err(), ParseMinefieldError::WrongNumber { x: 1, y: 1 });
+     }
+
+     #[test]
+     fn state_view() {
+        let mut minefield = Minefield::new(12, 2).with_mines_at(&[(11, 0)]);
+        assert_eq!(minefield.step(0, 1), StepResult::Phew);
+        assert_eq!(minefield.toggle_flag(11, 0), 1);
+
+        // Columns are as wide as the widest x label
+        let blanks = "   ".repeat(10);
+        let expected = [
+            "     0  1  2  3  4  5  6  7  8  9 10 11".to_string(),
+            format!("0 [{}  1  ⚐ ]", blanks),
+            format!("1 [{}  1  • ]", blanks),
+        ];
+        assert_eq!(minefield.state_view(), expected.join("\n") + "\n");
+     }
+
      #[allow(dead_code)]
      fn print_minefield(minefield: &Minefield) {
         // X axis
@@ -654,47 +1375,7 @@ impl Minefield {
 
      #[allow(dead_code)]
      fn print_minefield_state(minefield: &Minefield) {
-        // X axis
-        println!();
-        print!("   ");
-        for y in 0..minefield.width {
-            print!(" {}", y);
-        }
         println!();
-
-        for y in 0..minefield.height {
-            // Y Axis
-            print!("{:?} [", y);
-            for x in 0..minefield.width {
-                if let Some(index) = minefield.spot_index(x, y) {
-                    match minefield.field[index].state {
-                        SpotState::Hidden => {
-                            print!(" •");
-                        },
-                        SpotState::Flagged => {
-                            print!(" ⚐");
-                        },
-                        SpotState::Exploded => {
-                            print!(" 💥");
-                        }
-                        SpotState::Revealed => {
-                            match minefield.field[index].kind {
-                                SpotKind::Mine => {
-                                    print!(" ☢");
-                                },
-                                SpotKind::Empty(n) => {
-                                    if n > 0 {
-                                        print!(" {}", n);
-                                    } else {
-                                        print!("  ");
-                                    }
-                                },
-                            }
-                        },
-                    }
-                }
-            }
-            println!(" ]");
-        }
+        print!("{}", minefield.state_view());
      }
  }
\ No newline at end of file
@@ -0,0 +1,492 @@
+use crate::{minefield, replay, board_formats};
+
+use board_formats::BoardFormatError;
+use minefield::{Minefield, SpotKind, SpotState, StepResult};
+use replay::{Replay, ReplayAction, ReplayEvent};
+use std::fmt;
+
+/// Width (and height) of a spot in the pixel coordinates of the mouse events
+const SPOT_PIXELS: u32 = 16;
+
+/// A replay, along with who played it and when, as stored in a RAWVF file.
+///
+/// RAWVF is the plain text "raw video" format of the Minesweeper community: a header of `Name: value` lines, the
+/// mine layout (one line per row, `*` for mines and `0` for empty spots), and the mouse events of the game, one per
+/// line. For example:
+///
+/// ```text
+/// RawVF_Version: Rev2
+/// Program: minesweep-rs
+/// Player: Anonymous
+/// Timestamp: 2022-10-17 12:00:00
+/// Level: Custom
+/// Width: 3
+/// Height: 2
+/// Mines: 1
+/// Marks: Off
+/// Time: 1.500
+/// Board:
+/// 00*
+/// 000
+/// Events:
+/// 0.000 lc 8 8 (1 1)
+/// 0.000 lr 8 8 (1 1)
+/// 1.500 rc 40 8 (3 1)
+/// 1.500 rr 40 8 (3 1)
+/// 1.500 won
+/// ```
+///
+/// Event times are in seconds since the first move, spots are numbered from 1 and pixel coordinates assume spots of
+/// 16 pixels. Each move is written as a press and a release: `l` (left button) for steps, `r` (right button) for
+/// flags and `m` (middle button) for chords. When reading, chords made with both the left and right buttons are
+/// understood as well, and events which are not moves (such as `mv` for mouse moves) are ignored.
+///
+/// Replays always start from a fully hidden minefield in this format.
+#[derive(Clone, Debug)]
+pub struct ReplayFile {
+    pub player: String,
+
+    /// When the game was played, in any human readable form
+    pub timestamp: String,
+
+    pub replay: Replay,
+}
+
+/// Reasons for which a RAWVF replay can't be read or written
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RawVfError {
+    /// The header is missing the given field
+    MissingField(&'static str),
+
+    /// The line can't be understood (lines are numbered from 1)
+    Syntax { line: usize },
+
+    /// The mine layout is invalid
+    Board(BoardFormatError),
+}
+
+impl fmt::Display for RawVfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawVfError::MissingField(name) => write!(f, "Invalid replay: missing {}", name),
+            RawVfError::Syntax { line } => write!(f, "Invalid replay at line {}", line),
+            RawVfError::Board(e) => write!(f, "Invalid replay board: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for RawVfError {}
+
+impl From<BoardFormatError> for RawVfError {
+    fn from(e: BoardFormatError) -> Self {
+        RawVfError::Board(e)
+    }
+}
+
+impl ReplayFile {
+    /// Write the replay in the RAWVF format. The mines must have been placed, i.e. the game must have started.
+    pub fn to_rawvf(&self) -> Result<String, RawVfError> {
+        let last = self.replay.minefield_at(self.replay.duration());
+        let mines = board_formats::mine_coords(&last)?;
+        let (width, height) = (last.width(), last.height());
+
+        let mut text = String::new();
+        text.push_str("RawVF_Version: Rev2\n");
+        text.push_str("Program: minesweep-rs\n");
+        text.push_str(&format!("Player: {}\n", self.player.trim()));
+        text.push_str(&format!("Timestamp: {}\n", self.timestamp.trim()));
+        text.push_str(&format!("Level: {}\n", level(width, height, mines.len())));
+        text.push_str(&format!("Width: {}\nHeight: {}\nMines: {}\n", width, height, mines.len()));
+        text.push_str("Marks: Off\n");
+        text.push_str(&format!("Time: {}\n", seconds(self.replay.duration())));
+
+        text.push_str("Board:\n");
+        for y in 0..height {
+            for x in 0..width {
+                let is_mine = last.spot(x, y).map(|spot| spot.kind()) == Some(SpotKind::Mine);
+                text.push(if is_mine { '*' } else { '0' });
+            }
+            text.push('\n');
+        }
+
+        text.push_str("Events:\n");
+        for event in &self.replay.events {
+            let button = match event.action {
+                ReplayAction::Step => 'l',
+                ReplayAction::Flag | ReplayAction::Unflag => 'r',
+                ReplayAction::Chord => 'm',
+            };
+            let (px, py) = (event.x as u32 * SPOT_PIXELS + SPOT_PIXELS / 2, event.y as u32 * SPOT_PIXELS + SPOT_PIXELS / 2);
+
+            for press in ['c', 'r'] {
+                text.push_str(&format!(
+                    "{} {}{} {} {} ({} {})\n",
+                    seconds(event.millis),
+                    button,
+                    press,
+                    px,
+                    py,
+                    event.x + 1,
+                    event.y + 1
+                ));
+            }
+        }
+
+        if last.is_exploded() {
+            text.push_str(&format!("{} boom\n", seconds(self.replay.duration())));
+        } else if last.is_cleared() {
+            text.push_str(&format!("{} won\n", seconds(self.replay.duration())));
+        }
+
+        Ok(text)
+    }
+
+    /// Read a replay in the RAWVF format, turning its mouse events into moves
+    pub fn from_rawvf(text: &str) -> Result<Self, RawVfError> {
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line.trim()));
+
+        // Header
+        let (mut player, mut timestamp) = (String::new(), String::new());
+        let (mut width, mut height, mut mines) = (None, None, None);
+        loop {
+            let (line, text) = lines.next().ok_or(RawVfError::MissingField("Board"))?;
+            if text == "Board:" {
+                break;
+            }
+            if text.is_empty() {
+                continue;
+            }
+
+            let (name, value) = text.split_once(':').ok_or(RawVfError::Syntax { line })?;
+            let value = value.trim();
+            let number = || value.parse::<usize>().map_err(|_| RawVfError::Syntax { line });
+            match name.trim() {
+                "Player" => player = value.to_string(),
+                "Timestamp" => timestamp = value.to_string(),
+                "Width" => width = Some(number()?),
+                "Height" => height = Some(number()?),
+                "Mines" => mines = Some(number()?),
+                _ => {},
+            }
+        }
+
+        let width = width.ok_or(RawVfError::MissingField("Width"))?;
+        let height = height.ok_or(RawVfError::MissingField("Height"))?;
+        let mines = mines.ok_or(RawVfError::MissingField("Mines"))?;
+
+        // Board
+        let mut mine_coords = vec![];
+        for y in 0..height {
+            let (line, row) = lines.next().ok_or(BoardFormatError::Truncated)?;
+            if row.chars().count() != width {
+                return Err(RawVfError::Syntax { line });
+            }
+
+            for (x, symbol) in row.chars().enumerate() {
+                match symbol {
+                    '*' => mine_coords.push((x, y)),
+                    '0' => {},
+                    _ => return Err(RawVfError::Syntax { line }),
+                }
+            }
+        }
+
+        if mine_coords.len() != mines {
+            return Err(BoardFormatError::MineCount { expected: mines, found: mine_coords.len() }.into());
+        }
+        let start = board_formats::build_minefield(width, height, mine_coords.into_iter())?;
+
+        // Events
+        match lines.find(|(_, text)| !text.is_empty()) {
+            Some((_, "Events:")) => {},
+            Some((line, _)) => return Err(RawVfError::Syntax { line }),
+            None => return Err(RawVfError::MissingField("Events")),
+        }
+
+        let mut mouse = Mouse::new(start.clone());
+        for (line, text) in lines.filter(|(_, text)| !text.is_empty()) {
+            let mouse_event = MouseEvent::parse(text).ok_or(RawVfError::Syntax { line })?;
+            mouse.handle(mouse_event);
+        }
+
+        Ok(Self { player, timestamp, replay: Replay { seed: 0, start, events: mouse.events } })
+    }
+}
+
+/// A mouse event of a RAWVF replay
+struct MouseEvent<'a> {
+    millis: u64,
+    kind: &'a str,
+    spot: Option<(u16, u16)>,
+}
+
+impl<'a> MouseEvent<'a> {
+    /// Parse an event line, e.g. `1.234 lc 40 8 (3 1)`. The spot is taken from the numbers in parentheses if there
+    /// are any, or from the pixel coordinates otherwise.
+    fn parse(text: &'a str) -> Option<Self> {
+        let mut parts = text.split_whitespace();
+
+        // Some tools write events before the first move with negative times
+        let seconds: f64 = parts.next()?.parse().ok()?;
+        let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+        let kind = parts.next()?;
+
+        let numbers: Vec<&str> = parts.collect();
+        let spot = match numbers.as_slice() {
+            [_, _, col, row] => {
+                let col: u16 = col.trim_start_matches('(').parse().ok()?;
+                let row: u16 = row.trim_end_matches(')').parse().ok()?;
+                Some((col.checked_sub(1)?, row.checked_sub(1)?))
+            },
+            [px, py] => {
+                let px: u32 = px.parse().ok()?;
+                let py: u32 = py.parse().ok()?;
+                Some(((px / SPOT_PIXELS) as u16, (py / SPOT_PIXELS) as u16))
+            },
+            [] => None,
+            _ => return None,
+        };
+
+        Some(Self { millis, kind, spot })
+    }
+}
+
+/// Turns the mouse events of a replay into moves, by following the buttons and playing the moves on the minefield
+struct Mouse {
+    minefield: Minefield,
+    events: Vec<ReplayEvent>,
+    left_down: bool,
+    right_down: bool,
+
+    /// A chord was made with both buttons, so releasing the other button is not a move
+    chorded: bool,
+}
+
+impl Mouse {
+    fn new(minefield: Minefield) -> Self {
+        Self { minefield, events: vec![], left_down: false, right_down: false, chorded: false }
+    }
+
+    fn handle(&mut self, mouse_event: MouseEvent) {
+        // Nothing happens once the game is over
+        if self.minefield.is_exploded() || self.minefield.is_cleared() {
+            return;
+        }
+
+        let (x, y) = match mouse_event.spot {
+            Some(spot) => spot,
+            None => return,
+        };
+        let millis = mouse_event.millis;
+
+        match mouse_event.kind {
+            "lc" => self.left_down = true,
+            "rc" => {
+                self.right_down = true;
+                if !self.left_down {
+                    let action = match self.minefield.toggle_flag(x, y) {
+                        1 => ReplayAction::Flag,
+                        -1 => ReplayAction::Unflag,
+                        _ => return,
+                    };
+                    self.events.push(ReplayEvent { millis, action, x, y });
+                }
+            },
+            "lr" => {
+                self.left_down = false;
+                if self.right_down {
+                    self.chord(millis, x, y);
+                } else if self.chorded {
+                    self.chorded = false;
+                } else if self.minefield.spot(x, y).map(|spot| spot.state()) == Some(SpotState::Hidden) {
+                    // Clicks on revealed or flagged spots do nothing
+                    self.minefield.step(x, y);
+                    self.events.push(ReplayEvent { millis, action: ReplayAction::Step, x, y });
+                }
+            },
+            "rr" => {
+                self.right_down = false;
+                if self.left_down {
+                    self.chord(millis, x, y);
+                } else {
+                    self.chorded = false;
+                }
+            },
+            "mr" => self.chord(millis, x, y),
+            _ => {},
+        }
+    }
+
+    fn chord(&mut self, millis: u64, x: u16, y: u16) {
+        self.chorded = true;
+        if self.minefield.try_resolve_step(x, y) != StepResult::Invalid {
+            self.events.push(ReplayEvent { millis, action: ReplayAction::Chord, x, y });
+        }
+    }
+}
+
+/// Format milliseconds as seconds
+fn seconds(millis: u64) -> String {
+    format!("{}.{:03}", millis / 1000, millis % 1000)
+}
+
+/// Name of the standard level with the given board, if any
+fn level(width: u16, height: u16, mines: usize) -> &'static str {
+    match (width, height, mines) {
+        (8, 8, 10) | (9, 9, 10) => "Beginner",
+        (16, 16, 40) => "Intermediate",
+        (30, 16, 99) => "Expert",
+        _ => "Custom",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use replay::Recorder;
+    use minefield::FirstStepPolicy;
+
+    /// Check that two minefields have the same mines
+    fn same_layout(a: &Minefield, b: &Minefield) -> bool {
+        let is_mine = |minefield: &Minefield, x, y| minefield.spot(x, y).unwrap().kind() == SpotKind::Mine;
+
+        a.width() == b.width()
+            && a.height() == b.height()
+            && (0..a.height()).all(|y| (0..a.width()).all(|x| is_mine(a, x, y) == is_mine(b, x, y)))
+    }
+
+    #[test]
+    fn round_trip() {
+        // A game with deferred mines, which are placed by the first step
+        let start = Minefield::new(9, 9).with_mines_deferred(10, 42, FirstStepPolicy::SafeNeighborhood);
+        let mut minefield = start.clone();
+        let mut recorder = Recorder::new(42, start);
+
+        let result = minefield.step(4, 4);
+        recorder.record_step(10_000, ReplayAction::Step, 4, 4, result);
+        let flags_change = minefield.toggle_flag(0, 0);
+        recorder.record_flag(11_234, 0, 0, flags_change);
+        let flags_change = minefield.toggle_flag(0, 0);
+        recorder.record_flag(12_000, 0, 0, flags_change);
+
+        // Step on every hidden mine-free spot, until the game is over
+        let mut millis = 13_000;
+        for y in 0..9 {
+            for x in 0..9 {
+                let spot = minefield.spot(x, y).unwrap();
+                if spot.kind() != SpotKind::Mine && spot.state() == SpotState::Hidden {
+                    let result = minefield.step(x, y);
+                    recorder.record_step(millis, ReplayAction::Step, x, y, result);
+                    millis += 7;
+                }
+            }
+        }
+
+        let replay_file = ReplayFile {
+            player: "Bogdan".to_string(),
+            timestamp: "2022-10-17 12:00:00".to_string(),
+            replay: recorder.replay().clone(),
+        };
+        let text = replay_file.to_rawvf().unwrap();
+        assert!(text.contains("Player: Bogdan\n"));
+        assert!(text.contains("Level: Beginner\nWidth: 9\nHeight: 9\nMines: 10\n"));
+        assert!(text.contains("\n0.000 lc 72 72 (5 5)\n0.000 lr 72 72 (5 5)\n1.234 rc 8 8 (1 1)\n1.234 rr 8 8 (1 1)\n"));
+
+        let loaded = ReplayFile::from_rawvf(&text).unwrap();
+        assert_eq!(loaded.player, "Bogdan");
+        assert_eq!(loaded.timestamp, "2022-10-17 12:00:00");
+        assert_eq!(loaded.replay.events, replay_file.replay.events);
+        assert!(same_layout(&loaded.replay.start, &minefield));
+        assert_eq!(
+            loaded.replay.minefield_at(loaded.replay.duration()).to_string(),
+            replay_file.replay.minefield_at(replay_file.replay.duration()).to_string()
+        );
+
+        // Writing the loaded replay gives back the same file
+        assert_eq!(loaded.to_rawvf().unwrap(), text);
+    }
+
+    #[test]
+    fn third_party_replay() {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let text = "
+            RawVF_Version: Rev2
+            Program: Other sweeper
+            Player: Somebody
+            Width: 3
+            Height: 4
+            Mines: 2
+            Board:
+            00*
+            000
+            000
+            *00
+            Events:
+            -0.500 mv 3 3 (1 1)
+            0.000 lc 8 8 (1 1)
+            0.000 start
+            0.010 lr 8 8 (1 1)
+            0.500 mv 8 56 (1 4)
+            0.700 rc 8 56 (1 4)
+            0.800 rr 8 56 (1 4)
+            0.900 lc 24 24 (2 2)
+            1.000 lr 24 24 (2 2)
+            1.100 lc 24 40 (2 3)
+            1.200 rc 24 40 (2 3)
+            1.300 lr 24 40 (2 3)
+            1.400 rr 24 40 (2 3)
+            1.500 rc 40 8
+            1.600 rr 40 8
+            1.600 won
+        ";
+
+        let loaded = ReplayFile::from_rawvf(text).unwrap();
+        assert_eq!(loaded.player, "Somebody");
+        assert_eq!(loaded.timestamp, "");
+
+        // Stepping on a revealed spot is not a move, and the chord with both buttons is a single move
+        let actions: Vec<(u64, ReplayAction, u16, u16)> = loaded
+            .replay
+            .events
+            .iter()
+            .map(|event| (event.millis, event.action, event.x, event.y))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                (10, ReplayAction::Step, 0, 0),
+                (700, ReplayAction::Flag, 0, 3),
+                (1_300, ReplayAction::Chord, 1, 2),
+                (1_500, ReplayAction::Flag, 2, 0),
+            ]
+        );
+        assert!(loaded.replay.minefield_at(loaded.replay.duration()).is_cleared());
+    }
+
+    #[test]
+    fn invalid_replays() {
+        let board = "Width: 3\nHeight: 1\nMines: 1\nBoard:\n00*\n";
+        assert!(ReplayFile::from_rawvf(&format!("{}Events:\n0.000 lc 8 8 (1 1)\n", board)).is_ok());
+
+        assert_eq!(ReplayFile::from_rawvf("").unwrap_err(), RawVfError::MissingField("Board"));
+        assert_eq!(ReplayFile::from_rawvf("Width: 3\nBoard:\n").unwrap_err(), RawVfError::MissingField("Height"));
+        assert_eq!(ReplayFile::from_rawvf(board).unwrap_err(), RawVfError::MissingField("Events"));
+        assert_eq!(
+            ReplayFile::from_rawvf(&format!("{}Events:\nsoon lc 8 8 (1 1)\n", board)).unwrap_err(),
+            RawVfError::Syntax { line: 7 }
+        );
+        assert_eq!(
+            ReplayFile::from_rawvf("Width: 3\nHeight: 1\nMines: 2\nBoard:\n00*\nEvents:\n").unwrap_err(),
+            RawVfError::Board(BoardFormatError::MineCount { expected: 2, found: 1 })
+        );
+
+        // A replay can't be written before the mines are placed
+        let start = Minefield::new(9, 9).with_mines_deferred(10, 1, FirstStepPolicy::SafeSpot);
+        let replay_file = ReplayFile { player: String::new(), timestamp: String::new(), replay: Replay::new(1, start) };
+        assert_eq!(replay_file.to_rawvf().unwrap_err(), RawVfError::Board(BoardFormatError::PendingMines));
+    }
+}
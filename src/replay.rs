@@ -0,0 +1,381 @@
+use crate::minefield;
+
+use minefield::{Minefield, StepResult};
+use serde::{Serialize, Deserialize};
+
+/// A move made by the player
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ReplayAction {
+    /// Step on a hidden spot
+    Step,
+
+    /// Reveal the neighbors of a revealed spot, whose mines are all flagged
+    Chord,
+
+    /// Flag a hidden spot
+    Flag,
+
+    /// Remove the flag from a spot
+    Unflag,
+}
+
+/// A move, along with the spot it was made on and the time it was made at
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ReplayEvent {
+    /// Milliseconds since the first move of the game
+    pub millis: u64,
+    pub action: ReplayAction,
+    pub x: u16,
+    pub y: u16,
+}
+
+impl ReplayEvent {
+    /// Make the move on the given minefield
+    fn apply(&self, minefield: &mut Minefield) {
+        match self.action {
+            ReplayAction::Step => {
+                minefield.step(self.x, self.y);
+            },
+            ReplayAction::Chord => {
+                minefield.try_resolve_step(self.x, self.y);
+            },
+            ReplayAction::Flag | ReplayAction::Unflag => {
+                minefield.toggle_flag(self.x, self.y);
+            },
+        }
+    }
+}
+
+/// A recorded game: the minefield it started from, and the moves made on it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    /// Seed the mines were placed with
+    pub seed: u64,
+
+    /// The minefield before the first move. Recordings take the mines as the first step placed them, but replays
+    /// recorded before that may still have pending mines, which are then placed exactly as they were during the game
+    /// (see `place_mines()`).
+    pub start: Minefield,
+
+    /// The moves of the game, in the order they were made
+    pub events: Vec<ReplayEvent>,
+}
+
+impl Replay {
+    /// Start a replay with no moves
+    pub fn new(seed: u64, start: Minefield) -> Self {
+        Self { seed, start, events: vec![] }
+    }
+
+    /// Milliseconds from the first to the last move of the game
+    pub fn duration(&self) -> u64 {
+        self.events.last().map(|event| event.millis).unwrap_or(0)
+    }
+
+    /// Place the pending mines of the start minefield (if any) the way the first step of the game placed them, so that
+    /// playing the replay doesn't have to place them again
+    pub fn place_mines(&mut self) {
+        if !self.start.has_pending_mines() {
+            return;
+        }
+
+        let mut minefield = self.start.clone();
+        for event in &self.events {
+            event.apply(&mut minefield);
+            if !minefield.has_pending_mines() {
+                self.start = self.start.clone().with_mines_of(&minefield);
+                break;
+            }
+        }
+    }
+
+    /// The minefield as it was at the given time of the game
+    pub fn minefield_at(&self, millis: u64) -> Minefield {
+        let mut minefield = self.start.clone();
+        for event in self.events.iter().take_while(|event| event.millis <= millis) {
+            event.apply(&mut minefield);
+        }
+
+        minefield
+    }
+}
+
+/// Records the moves of a game as it is being played. Moves which are undone are dropped from the recording (and put
+/// back if they are redone), so that the replay always leads to the current state of the game.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    replay: Replay,
+
+    /// Clock time of the first move, in milliseconds
+    start_millis: Option<u64>,
+
+    /// Moves which were undone, most recently undone last
+    undone: Vec<ReplayEvent>,
+}
+
+impl Recorder {
+    /// Start recording a game played on the given minefield
+    pub fn new(seed: u64, start: Minefield) -> Self {
+        Self { replay: Replay::new(seed, start), start_millis: None, undone: vec![] }
+    }
+
+    /// The recording so far
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Record a move, made at the given time of a monotonic clock (in milliseconds)
+    pub fn record(&mut self, now_millis: u64, action: ReplayAction, x: u16, y: u16) {
+        let millis = self.millis_since_start(now_millis);
+        self.replay.events.push(ReplayEvent { millis, action, x, y });
+        self.undone.clear();
+    }
+
+    /// Record a step (or a chord), if it was a valid move
+    pub fn record_step(&mut self, now_millis: u64, action: ReplayAction, x: u16, y: u16, result: StepResult) {
+        if result != StepResult::Invalid {
+            self.record(now_millis, action, x, y);
+        }
+    }
+
+    /// Record a flag being toggled, given the change in the number of flags (see `Minefield::toggle_flag()`)
+    pub fn record_flag(&mut self, now_millis: u64, x: u16, y: u16, flags_change: i32) {
+        match flags_change {
+            1 => self.record(now_millis, ReplayAction::Flag, x, y),
+            -1 => self.record(now_millis, ReplayAction::Unflag, x, y),
+            _ => {},
+        }
+    }
+
+    /// Take the mines of the game's minefield, once its first step placed them, as the mines of the start minefield.
+    /// The replay then starts from the mines of the game, without placing them again.
+    pub fn take_mines(&mut self, minefield: &Minefield) {
+        if !minefield.has_pending_mines() {
+            self.replay.start = self.replay.start.clone().with_mines_of(minefield);
+        }
+    }
+
+    /// Drop the last move, which was undone
+    pub fn undo(&mut self) {
+        if let Some(event) = self.replay.events.pop() {
+            self.undone.push(event);
+        }
+    }
+
+    /// Put back the last undone move, which was redone at the given time
+    pub fn redo(&mut self, now_millis: u64) {
+        if let Some(event) = self.undone.pop() {
+            let millis = self.millis_since_start(now_millis);
+            self.replay.events.push(ReplayEvent { millis, ..event });
+        }
+    }
+
+    fn millis_since_start(&mut self, now_millis: u64) -> u64 {
+        let start_millis = *self.start_millis.get_or_insert(now_millis);
+
+        now_millis.saturating_sub(start_millis)
+    }
+}
+
+/// Plays back a replay, keeping track of the minefield at the current position
+#[derive(Clone, Debug)]
+pub struct Player {
+    replay: Replay,
+    minefield: Minefield,
+
+    /// Number of events applied to the minefield
+    applied: usize,
+
+    /// Current position in the replay, in milliseconds
+    position: f64,
+}
+
+impl Player {
+    /// Start playing a replay from its beginning
+    pub fn new(mut replay: Replay) -> Self {
+        // Going back plays again from the start, which is cheaper with the mines already placed
+        replay.place_mines();
+        let minefield = replay.start.clone();
+
+        Self { replay, minefield, applied: 0, position: 0.0 }
+    }
+
+    /// The replay being played
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// The minefield at the current position
+    pub fn minefield(&self) -> &Minefield {
+        &self.minefield
+    }
+
+    /// Current position in the replay, in milliseconds
+    pub fn position(&self) -> u64 {
+        self.position as u64
+    }
+
+    /// Check if the position is past the last move
+    pub fn is_finished(&self) -> bool {
+        self.applied == self.replay.events.len()
+    }
+
+    /// Move the position forward by the given number of milliseconds
+    pub fn advance(&mut self, millis: f64) {
+        self.seek_precise(self.position + millis.max(0.0));
+    }
+
+    /// Move the position to the given time of the game, either forward or back
+    pub fn seek(&mut self, millis: u64) {
+        self.seek_precise(millis as f64);
+    }
+
+    fn seek_precise(&mut self, position: f64) {
+        let position = position.min(self.replay.duration() as f64);
+
+        // Moves can't be taken back, so going back means playing again from the start
+        if position < self.position {
+            self.minefield = self.replay.start.clone();
+            self.applied = 0;
+        }
+
+        self.position = position;
+
+        while let Some(event) = self.replay.events.get(self.applied) {
+            if event.millis as f64 > position {
+                break;
+            }
+
+            event.apply(&mut self.minefield);
+            self.applied += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minefield::SpotState;
+
+    #[test]
+    fn record_and_play() {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let mut minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        let mut recorder = Recorder::new(0, minefield.clone());
+
+        // Invalid moves are not recorded
+        let result = minefield.step(5, 5);
+        recorder.record_step(1_000, ReplayAction::Step, 5, 5, result);
+        let flags_change = minefield.toggle_flag(5, 5);
+        recorder.record_flag(1_000, 5, 5, flags_change);
+        assert!(recorder.replay().events.is_empty());
+
+        let result = minefield.step(0, 0);
+        recorder.record_step(1_500, ReplayAction::Step, 0, 0, result);
+        let flags_change = minefield.toggle_flag(2, 0);
+        recorder.record_flag(2_000, 2, 0, flags_change);
+        let flags_change = minefield.toggle_flag(2, 0);
+        recorder.record_flag(2_500, 2, 0, flags_change);
+        let flags_change = minefield.toggle_flag(2, 0);
+        recorder.record_flag(3_000, 2, 0, flags_change);
+        let flags_change = minefield.toggle_flag(2, 2);
+        recorder.record_flag(4_000, 2, 2, flags_change);
+        let result = minefield.try_resolve_step(1, 2);
+        recorder.record_step(4_500, ReplayAction::Chord, 1, 2, result);
+        assert!(minefield.is_exploded());
+
+        let replay = recorder.replay().clone();
+        let actions: Vec<(u64, ReplayAction)> = replay.events.iter().map(|event| (event.millis, event.action)).collect();
+        assert_eq!(
+            actions,
+            vec![
+                (0, ReplayAction::Step),
+                (500, ReplayAction::Flag),
+                (1_000, ReplayAction::Unflag),
+                (1_500, ReplayAction::Flag),
+                (2_500, ReplayAction::Flag),
+                (3_000, ReplayAction::Chord),
+            ]
+        );
+        assert_eq!(replay.duration(), 3_000);
+
+        // The replay leads to the same minefield as the game
+        assert_eq!(replay.minefield_at(replay.duration()).to_string(), minefield.to_string());
+        assert_eq!(replay.minefield_at(600).spot(2, 0).unwrap().state(), SpotState::Flagged);
+        assert_eq!(replay.minefield_at(1_200).spot(2, 0).unwrap().state(), SpotState::Hidden);
+
+        // Play back, forward and back
+        let mut player = Player::new(replay.clone());
+        assert_eq!(player.minefield().spot(0, 0).unwrap().state(), SpotState::Hidden);
+        player.advance(0.0);
+        assert_eq!(player.minefield().spot(0, 0).unwrap().state(), SpotState::Revealed);
+        player.advance(700.0);
+        assert_eq!(player.position(), 700);
+        assert_eq!(player.minefield().to_string(), replay.minefield_at(700).to_string());
+        player.seek(2_600);
+        assert_eq!(player.minefield().to_string(), replay.minefield_at(2_600).to_string());
+        assert!(!player.is_finished());
+        player.seek(1_200);
+        assert_eq!(player.minefield().to_string(), replay.minefield_at(1_200).to_string());
+        player.advance(10_000.0);
+        assert_eq!(player.position(), 3_000);
+        assert!(player.is_finished());
+        assert!(player.minefield().is_exploded());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        let mut recorder = Recorder::new(0, minefield.clone());
+
+        let result = minefield.step(0, 0);
+        recorder.record_step(0, ReplayAction::Step, 0, 0, result);
+        let flags_change = minefield.toggle_flag(2, 0);
+        recorder.record_flag(100, 2, 0, flags_change);
+
+        // Undone moves are dropped, and put back when redone
+        recorder.undo();
+        assert_eq!(recorder.replay().events.len(), 1);
+        recorder.redo(300);
+        assert_eq!(recorder.replay().events.len(), 2);
+        assert_eq!(recorder.replay().events[1].millis, 300);
+        assert_eq!(recorder.replay().events[1].action, ReplayAction::Flag);
+
+        // A new move discards the moves which could have been redone
+        recorder.undo();
+        let result = minefield.step(2, 3);
+        recorder.record_step(400, ReplayAction::Step, 2, 3, result);
+        recorder.redo(500);
+        assert_eq!(recorder.replay().events.len(), 2);
+        assert_eq!(recorder.replay().events[1].action, ReplayAction::Step);
+    }
+
+    #[test]
+    fn pending_mines() {
+        // The mines of the replay are placed by the first step, just like they were during the game
+        let start = Minefield::new(9, 9).with_mines_deferred(10, 42, minefield::FirstStepPolicy::SafeNeighborhood);
+        let mut minefield = start.clone();
+        let mut recorder = Recorder::new(42, start);
+
+        let result = minefield.step(4, 4);
+        recorder.record_step(0, ReplayAction::Step, 4, 4, result);
+
+        assert_eq!(recorder.replay().minefield_at(0).to_string(), minefield.to_string());
+
+        // Playing places them once, up front
+        let player = Player::new(recorder.replay().clone());
+        assert!(!player.replay().start.has_pending_mines());
+        assert_eq!(player.replay().minefield_at(0).to_string(), minefield.to_string());
+
+        // Once the recording takes the mines of the game, they are never placed again
+        recorder.take_mines(&minefield);
+        let replay = recorder.replay();
+        assert!(!replay.start.has_pending_mines());
+        assert_eq!(replay.start.spot(4, 4).unwrap().state(), SpotState::Hidden);
+        assert_eq!(replay.minefield_at(0).to_string(), minefield.to_string());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::{game, metrics, minefield};
+
+use game::{GameConfig, GameState};
+use metrics::Clicks;
+use minefield::{Minefield, SpotState};
+use serde::{Serialize, Deserialize};
+use std::{fmt, time::Duration};
+
+/// Version of the saved game format. It must be increased whenever the format changes in an incompatible way.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A game (possibly in progress), as saved to the app storage or to a file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    /// Version of the format the game was saved in
+    pub version: u32,
+
+    pub game_config: GameConfig,
+    pub seed: u64,
+    pub minefield: Minefield,
+    pub game_state: GameState,
+    pub seconds_lapsed: i32,
+
+    /// Play time with millisecond precision, missing from games saved before it was counted
+    #[serde(default)]
+    pub millis_lapsed: Option<u64>,
+
+    pub placed_flags: i32,
+    pub hints_used: u32,
+    pub undos_used: u32,
+
+    /// Clicks made so far, missing from games saved before they were counted
+    #[serde(default)]
+    pub clicks: Clicks,
+}
+
+/// Reasons for which a saved game can't be loaded
+#[derive(Debug)]
+pub enum SaveError {
+    /// The saved game could not be read or written
+    Io(std::io::Error),
+
+    /// The saved game is not valid JSON, or is missing some data
+    Format(serde_json::Error),
+
+    /// The saved game was written in an unsupported format version
+    Version(u32),
+
+    /// The saved minefield is inconsistent
+    Minefield,
+
+    /// The saved game state or flag count contradicts the saved minefield
+    State,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "Could not access the saved game: {}", e),
+            SaveError::Format(e) => write!(f, "Invalid saved game: {}", e),
+            SaveError::Version(version) => {
+                write!(f, "Unsupported saved game version {} (expected {})", version, FORMAT_VERSION)
+            },
+            SaveError::Minefield => write!(f, "Invalid saved game: the minefield is inconsistent"),
+            SaveError::State => write!(f, "Invalid saved game: the game state doesn't match the minefield"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(e: std::io::Error) -> Self {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveError::Format(e)
+    }
+}
+
+impl SavedGame {
+    /// Play time, as precise as it was saved
+    pub fn elapsed(&self) -> Duration {
+        match self.millis_lapsed {
+            Some(millis) => Duration::from_millis(millis),
+            None => Duration::from_secs(self.seconds_lapsed.max(0) as u64),
+        }
+    }
+
+    /// Check that the saved game can be loaded
+    pub fn validate(self) -> Result<Self, SaveError> {
+        if self.version != FORMAT_VERSION {
+            Err(SaveError::Version(self.version))
+        } else if !self.minefield.is_well_formed() {
+            Err(SaveError::Minefield)
+        } else if !self.is_state_consistent() {
+            Err(SaveError::State)
+        } else {
+            Ok(self)
+        }
+    }
+
+    /// Check that the game state and the flag count agree with the minefield: a game is lost if and only if a mine
+    /// exploded, won if and only if the minefield is cleared, and only ready if no move was made
+    fn is_state_consistent(&self) -> bool {
+        let minefield = &self.minefield;
+        let is_started = minefield.spot_states().iter().any(|state| *state != SpotState::Hidden);
+
+        let expected_state = if minefield.is_exploded() {
+            self.game_state == GameState::Stopped(false)
+        } else if minefield.is_cleared() {
+            self.game_state == GameState::Stopped(true)
+        } else {
+            match self.game_state {
+                GameState::Ready => !is_started,
+                GameState::Running => true,
+                GameState::Stopped(_) => false,
+            }
+        };
+
+        expected_state && self.placed_flags == minefield.flags() as i32
+    }
+
+    /// Serialize the saved game to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Saved games can always be serialized")
+    }
+
+    /// Deserialize and validate a saved game from JSON
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        // Check the version first, so that games saved in other versions are reported as such, rather than as
+        // being malformed
+        #[derive(Deserialize)]
+        struct Versioned {
+            version: u32,
+        }
+
+        let versioned: Versioned = serde_json::from_str(json)?;
+        if versioned.version != FORMAT_VERSION {
+            return Err(SaveError::Version(versioned.version));
+        }
+
+        serde_json::from_str::<SavedGame>(json)?.validate()
+    }
+
+    /// Write the saved game to a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_to(&self, path: &std::path::Path) -> Result<(), SaveError> {
+        std::fs::write(path, self.to_json())?;
+
+        Ok(())
+    }
+
+    /// Read a saved game from a file
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn read_from(path: &std::path::Path) -> Result<Self, SaveError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use minefield::{SpotState, StepResult};
+
+    fn saved_game() -> SavedGame {
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        // 2 [ 1 1   ]
+        // 3 [ ☢ 1   ]
+        let mut minefield = Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)]);
+        assert_eq!(minefield.step(0, 0), StepResult::Phew);
+        assert_eq!(minefield.toggle_flag(2, 0), 1);
+
+        SavedGame {
+            version: FORMAT_VERSION,
+            game_config: GameConfig::default(),
+            seed: 42,
+            minefield,
+            game_state: GameState::Running,
+            seconds_lapsed: 12,
+            millis_lapsed: Some(12_345),
+            placed_flags: 1,
+            hints_used: 0,
+            undos_used: 2,
+            clicks: Clicks { left: 1, right: 1, chord: 0 },
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let saved_game = saved_game();
+        let loaded = SavedGame::from_json(&saved_game.to_json()).unwrap();
+
+        assert_eq!(loaded.seed, 42);
+        assert_eq!(loaded.game_state, GameState::Running);
+        assert_eq!(loaded.elapsed(), Duration::from_millis(12_345));
+        assert_eq!(loaded.placed_flags, 1);
+        assert_eq!(loaded.undos_used, 2);
+
+        for y in 0..4 {
+            for x in 0..3 {
+                let spot = saved_game.minefield.spot(x, y).unwrap();
+                let loaded_spot = loaded.minefield.spot(x, y).unwrap();
+                assert_eq!(spot.kind(), loaded_spot.kind());
+                assert_eq!(spot.state(), loaded_spot.state());
+            }
+        }
+        assert_eq!(loaded.minefield.spot(2, 0).unwrap().state(), SpotState::Flagged);
+
+        // The game goes on after loading
+        let mut minefield = loaded.minefield;
+        assert_eq!(minefield.step(2, 2), StepResult::Phew);
+        assert_eq!(minefield.step(0, 3), StepResult::Boom);
+
+        // Games saved before the play time was counted in milliseconds
+        let json = saved_game.to_json().replace("\"millis_lapsed\": 12345", "\"millis_lapsed\": null");
+        assert_eq!(SavedGame::from_json(&json).unwrap().elapsed(), Duration::from_secs(12));
+    }
+
+    #[test]
+    fn pending_mines_round_trip() {
+        let mut saved_game = saved_game();
+        saved_game.minefield = GameConfig::default().minefield(7);
+        saved_game.game_state = GameState::Ready;
+        saved_game.placed_flags = 0;
+
+        let mut loaded = SavedGame::from_json(&saved_game.to_json()).unwrap();
+        assert!(loaded.minefield.has_pending_mines());
+
+        // Mines are placed on the first step, just like in the original minefield
+        let mut original = saved_game.minefield;
+        original.step(1, 1);
+        loaded.minefield.step(1, 1);
+        for y in 0..original.height() {
+            for x in 0..original.width() {
+                assert_eq!(original.spot(x, y).unwrap().kind(), loaded.minefield.spot(x, y).unwrap().kind());
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_saved_games() {
+        let json = saved_game().to_json();
+
+        // Unknown version
+        let newer = json.replacen(&format!("\"version\": {}", FORMAT_VERSION), "\"version\": 999", 1);
+        assert!(matches!(SavedGame::from_json(&newer), Err(SaveError::Version(999))));
+
+        // Not a saved game
+        assert!(matches!(SavedGame::from_json("{\"version\": 1}"), Err(SaveError::Format(_))));
+        assert!(matches!(SavedGame::from_json("not json"), Err(SaveError::Format(_))));
+
+        // Tampered minefield
+        let tampered = json.replacen("\"width\": 3", "\"width\": 4", 1);
+        assert!(matches!(SavedGame::from_json(&tampered), Err(SaveError::Minefield)));
+    }
+
+    #[test]
+    fn inconsistent_game_states() {
+        // A mine exploded, but the game is still running
+        let mut exploded = saved_game();
+        assert_eq!(exploded.minefield.step(0, 3), StepResult::Boom);
+        assert!(matches!(SavedGame::from_json(&exploded.to_json()), Err(SaveError::State)));
+        exploded.game_state = GameState::Stopped(false);
+        assert!(SavedGame::from_json(&exploded.to_json()).is_ok());
+
+        // The minefield is cleared, but the game is still running
+        let mut cleared = saved_game();
+        assert_eq!(cleared.minefield.step(2, 2), StepResult::Phew);
+        assert_eq!(cleared.minefield.toggle_flag(0, 3), 1);
+        cleared.placed_flags = 2;
+        assert!(matches!(SavedGame::from_json(&cleared.to_json()), Err(SaveError::State)));
+
+        // Moves were made, but the game is ready to start, or it is over without any mine exploding
+        for game_state in [GameState::Ready, GameState::Stopped(false), GameState::Stopped(true)] {
+            let tampered = SavedGame { game_state, ..saved_game() };
+            assert!(matches!(SavedGame::from_json(&tampered.to_json()), Err(SaveError::State)));
+        }
+
+        // The flag counter doesn't match the flags on the minefield
+        let tampered = SavedGame { placed_flags: 3, ..saved_game() };
+        assert!(matches!(SavedGame::from_json(&tampered.to_json()), Err(SaveError::State)));
+    }
+}
@@ -0,0 +1,479 @@
+use crate::{clock, game, history, metrics, minefield, save};
+
+use clock::{Clock, MonotonicClock, Stopwatch};
+use game::GameState;
+use history::History;
+use metrics::{Clicks, Metrics};
+use minefield::{Minefield, SpotState, StepResult};
+use save::SavedGame;
+use std::{rc::Rc, time::Duration};
+
+/// What a move did to the game
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MoveOutcome {
+    /// Result of the move on the minefield: `Phew` for flags and undone moves, `Invalid` for a chord without enough
+    /// flags around
+    pub step_result: StepResult,
+
+    /// Change in the number of placed flags
+    pub flags_change: i32,
+
+    /// Whether the move went into the history, so that it is the one taken back by the next undo. Moves which change
+    /// nothing are not recorded, and neither are undo and redo themselves.
+    pub recorded: bool,
+
+    /// State of the game before and after the move
+    pub previous_state: GameState,
+    pub state: GameState,
+}
+
+impl MoveOutcome {
+    /// Check if this was the first move of the game
+    pub fn started(&self) -> bool {
+        self.previous_state == GameState::Ready && self.state != GameState::Ready
+    }
+
+    /// Whether the game was won (`Some(true)`) or lost (`Some(false)`) by this move
+    pub fn ended(&self) -> Option<bool> {
+        match self.state {
+            GameState::Stopped(is_won) if self.state != self.previous_state => Some(is_won),
+            _ => None,
+        }
+    }
+}
+
+/// A game being played on a minefield: the rules of how moves change the state of the game, the flag counter and the
+/// elapsed time
+#[derive(Clone, Debug)]
+pub struct GameSession {
+    history: History,
+    game_state: GameState,
+    placed_flags: i32,
+    clicks: Clicks,
+
+    /// Play time, which runs while the game is running
+    stopwatch: Stopwatch,
+
+    /// A paused game takes no moves, and its clock is stopped
+    is_paused: bool,
+
+    /// Number of moves undone during the game
+    undos_used: u32,
+}
+
+impl GameSession {
+    /// Start playing on a given minefield, picking up from its play state: a board which was partially played is
+    /// already running, and may even be lost or won
+    pub fn new(minefield: Minefield) -> Self {
+        let is_started = (0..minefield.height())
+            .flat_map(|y| (0..minefield.width()).map(move |x| (x, y)))
+            .filter_map(|(x, y)| minefield.spot(x, y))
+            .any(|spot| spot.state() != SpotState::Hidden);
+
+        let mut session = Self {
+            history: History::new(minefield),
+            game_state: if is_started { GameState::Running } else { GameState::Ready },
+            placed_flags: 0,
+            clicks: Clicks::default(),
+            stopwatch: Stopwatch::new(Rc::new(MonotonicClock::new()), Duration::ZERO),
+            is_paused: false,
+            undos_used: 0,
+        };
+        session.sync_game_state();
+        session.sync_stopwatch();
+
+        session
+    }
+
+    /// Continue a saved game, in the state it was saved in (as far as the minefield agrees, see `SavedGame::validate()`)
+    pub fn from_saved_game(saved_game: &SavedGame) -> Self {
+        let mut session = Self {
+            history: History::new(saved_game.minefield.clone()),
+            game_state: saved_game.game_state,
+            placed_flags: saved_game.placed_flags,
+            clicks: saved_game.clicks,
+            stopwatch: Stopwatch::new(Rc::new(MonotonicClock::new()), saved_game.elapsed()),
+            is_paused: false,
+            undos_used: saved_game.undos_used,
+        };
+        session.sync_game_state();
+        session.sync_stopwatch();
+
+        session
+    }
+
+    /// Count the play time on the given clock, rather than on the monotonic clock of the system
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.stopwatch = Stopwatch::new(Rc::new(clock), self.stopwatch.elapsed());
+        self.sync_stopwatch();
+
+        self
+    }
+
+    /// The current state of the minefield
+    pub fn minefield(&self) -> &Minefield {
+        self.history.minefield()
+    }
+
+    pub fn state(&self) -> GameState {
+        self.game_state
+    }
+
+    /// Check if moves can still be made, i.e. the game is either ready or running
+    pub fn is_playing(&self) -> bool {
+        matches!(self.game_state, GameState::Ready | GameState::Running)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    /// Pause a running game. Returns `false` if the game isn't running, or is already paused.
+    pub fn pause(&mut self) -> bool {
+        if self.game_state != GameState::Running || self.is_paused {
+            return false;
+        }
+
+        self.is_paused = true;
+        self.sync_stopwatch();
+
+        true
+    }
+
+    /// Resume a paused game, exactly where it was left off. Returns `false` if the game wasn't paused.
+    pub fn resume(&mut self) -> bool {
+        if !self.is_paused {
+            return false;
+        }
+
+        self.is_paused = false;
+        self.sync_stopwatch();
+
+        true
+    }
+
+    /// The number of flags placed by the player, which may be more than the number of mines
+    pub fn placed_flags(&self) -> i32 {
+        self.placed_flags
+    }
+
+    /// Play time, from the first move until the game is over
+    pub fn elapsed(&self) -> Duration {
+        self.stopwatch.elapsed()
+    }
+
+    /// Play time, in whole seconds
+    pub fn seconds_lapsed(&self) -> i32 {
+        self.elapsed().as_secs() as i32
+    }
+
+    pub fn clicks(&self) -> Clicks {
+        self.clicks
+    }
+
+    pub fn undos_used(&self) -> u32 {
+        self.undos_used
+    }
+
+    /// Efficiency of the play so far, if the mines were placed
+    pub fn metrics(&self) -> Option<Metrics> {
+        self.minefield().bbbv().map(|bbbv| Metrics { bbbv, clicks: self.clicks })
+    }
+
+    /// Step on a hidden spot. Returns `None` if the spot can't be stepped on, or the game is over.
+    pub fn step(&mut self, x: u16, y: u16) -> Option<MoveOutcome> {
+        self.play(x, y, &[SpotState::Hidden], |clicks| clicks.left += 1, |session| (session.history.step(x, y), 0))
+    }
+
+    /// Place a flag on a hidden spot, or remove it from a flagged one. Returns `None` if the spot can't be flagged, or
+    /// the game is over.
+    pub fn flag(&mut self, x: u16, y: u16) -> Option<MoveOutcome> {
+        self.play(x, y, &[SpotState::Hidden, SpotState::Flagged], |clicks| clicks.right += 1, |session| {
+            let flags_change = session.history.toggle_flag(x, y);
+            session.placed_flags += flags_change;
+            (StepResult::Phew, flags_change)
+        })
+    }
+
+    /// Reveal the neighbors of a revealed spot, whose mines are all flagged (see `Minefield::try_resolve_step()`).
+    /// Returns `None` if the spot isn't revealed, or the game is over.
+    pub fn chord(&mut self, x: u16, y: u16) -> Option<MoveOutcome> {
+        self.play(x, y, &[SpotState::Revealed], |clicks| clicks.chord += 1, |session| {
+            (session.history.try_resolve_step(x, y), 0)
+        })
+    }
+
+    /// Check if there is a move which can be undone. A won game is final.
+    pub fn can_undo(&self) -> bool {
+        self.game_state != GameState::Stopped(true) && !self.is_paused && self.history.can_undo()
+    }
+
+    /// Check if there is an undone move which can be redone
+    pub fn can_redo(&self) -> bool {
+        self.game_state != GameState::Stopped(true) && !self.is_paused && self.history.can_redo()
+    }
+
+    /// Undo the last move. Undoing the move which lost the game brings it back to running, and the clock keeps counting
+    /// from where it stopped.
+    pub fn undo(&mut self) -> Option<MoveOutcome> {
+        if !self.can_undo() {
+            return None;
+        }
+
+        Some(self.change(|session| {
+            session.history.undo();
+            session.undos_used += 1;
+        }))
+    }
+
+    /// Redo the last undone move
+    pub fn redo(&mut self) -> Option<MoveOutcome> {
+        if !self.can_redo() {
+            return None;
+        }
+
+        Some(self.change(|session| {
+            session.history.redo();
+        }))
+    }
+
+    /// Make a move on a spot which is in one of the given states, starting the game if it wasn't. The click is counted
+    /// as long as the game is on, even if the spot isn't in one of the states and the move isn't made.
+    fn play(
+        &mut self,
+        x: u16,
+        y: u16,
+        states: &[SpotState],
+        count_click: impl FnOnce(&mut Clicks),
+        make_move: impl FnOnce(&mut Self) -> (StepResult, i32),
+    ) -> Option<MoveOutcome> {
+        let state = self.minefield().spot(x, y)?.state();
+        if !self.is_playing() || self.is_paused {
+            return None;
+        }
+
+        count_click(&mut self.clicks);
+        if !states.contains(&state) {
+            return None;
+        }
+
+        let previous_state = self.game_state;
+        if self.game_state == GameState::Ready {
+            self.game_state = GameState::Running;
+        }
+
+        let moves = self.history.moves();
+        let (step_result, flags_change) = make_move(self);
+        let recorded = self.history.moves() > moves;
+
+        if step_result == StepResult::Boom {
+            self.game_state = GameState::Stopped(false);
+        } else if self.minefield().is_cleared() {
+            self.game_state = GameState::Stopped(true);
+        }
+        self.sync_stopwatch();
+
+        Some(MoveOutcome { step_result, flags_change, recorded, previous_state, state: self.game_state })
+    }
+
+    /// Change the minefield through the history, and bring the game state in line with it
+    fn change(&mut self, change: impl FnOnce(&mut Self)) -> MoveOutcome {
+        let previous_state = self.game_state;
+        let previous_flags = self.placed_flags;
+
+        change(self);
+        self.sync_game_state();
+        self.sync_stopwatch();
+
+        MoveOutcome {
+            step_result: StepResult::Phew,
+            flags_change: self.placed_flags - previous_flags,
+            recorded: false,
+            previous_state,
+            state: self.game_state,
+        }
+    }
+
+    /// Run the stopwatch only while the game is running, and isn't paused
+    fn sync_stopwatch(&mut self) {
+        if self.game_state == GameState::Running && !self.is_paused {
+            self.stopwatch.start();
+        } else {
+            self.stopwatch.stop();
+        }
+    }
+
+    /// Bring the game state in line with the minefield
+    fn sync_game_state(&mut self) {
+        let minefield = self.history.minefield();
+        self.placed_flags = minefield.flags() as i32;
+
+        if minefield.is_exploded() {
+            self.game_state = GameState::Stopped(false);
+        } else if minefield.is_cleared() {
+            self.game_state = GameState::Stopped(true);
+        } else if let GameState::Stopped(_) = self.game_state {
+            self.game_state = GameState::Running;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clock::FakeClock;
+
+    //     0 1 2
+    // 0 [   1 ☢ ]
+    // 1 [   1 1 ]
+    // 2 [ 1 1   ]
+    // 3 [ ☢ 1   ]
+    fn minefield() -> Minefield {
+        Minefield::new(3, 4).with_mines_at(&[(2, 0), (0, 3)])
+    }
+
+    #[test]
+    fn win() {
+        let clock = FakeClock::default();
+        let mut session = GameSession::new(minefield()).with_clock(clock.clone());
+        assert_eq!(session.state(), GameState::Ready);
+
+        // Moves which aren't allowed change nothing (but count as clicks), and the clock only runs from the first move
+        assert_eq!(session.chord(0, 0), None);
+        assert_eq!(session.step(5, 5), None);
+        assert_eq!(session.state(), GameState::Ready);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(session.elapsed(), Duration::ZERO);
+
+        let outcome = session.step(0, 0).unwrap();
+        assert!(outcome.started());
+        assert_eq!(outcome.ended(), None);
+        assert_eq!(outcome.state, GameState::Running);
+        assert_eq!(session.step(0, 0), None);
+        clock.advance(Duration::from_millis(2_345));
+        assert_eq!(session.elapsed(), Duration::from_millis(2_345));
+        assert_eq!(session.seconds_lapsed(), 2);
+
+        // Flags are counted both ways
+        assert_eq!(session.flag(2, 0).unwrap().flags_change, 1);
+        assert_eq!(session.flag(0, 3).unwrap().flags_change, 1);
+        assert_eq!(session.flag(0, 3).unwrap().flags_change, -1);
+        assert_eq!(session.placed_flags(), 1);
+
+        // Clearing the board wins, only once all the mines are flagged
+        assert_eq!(session.step(2, 3).unwrap().ended(), None);
+        let outcome = session.flag(0, 3).unwrap();
+        assert!(!outcome.started());
+        assert_eq!(outcome.ended(), Some(true));
+        assert_eq!(session.state(), GameState::Stopped(true));
+
+        // A won game is final, and its clock stopped
+        assert_eq!(session.flag(0, 3), None);
+        assert!(!session.can_undo());
+        assert_eq!(session.undo(), None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(session.elapsed(), Duration::from_millis(2_345));
+        assert_eq!(session.clicks(), Clicks { left: 3, right: 4, chord: 1 });
+        assert_eq!(session.metrics().map(|metrics| metrics.bbbv), minefield().bbbv());
+    }
+
+    #[test]
+    fn lose_and_undo() {
+        let clock = FakeClock::default();
+        let mut session = GameSession::new(minefield()).with_clock(clock.clone());
+        session.step(1, 2).unwrap();
+
+        // A chord without enough flags around is a move, which changes nothing
+        let outcome = session.chord(1, 2).unwrap();
+        assert_eq!(outcome.step_result, StepResult::Invalid);
+        assert_eq!(outcome.state, GameState::Running);
+        assert_eq!(session.clicks().chord, 1);
+
+        // A misplaced flag makes the chord step on a mine
+        session.flag(0, 1).unwrap();
+        let outcome = session.chord(1, 2).unwrap();
+        assert_eq!(outcome.step_result, StepResult::Boom);
+        assert_eq!(outcome.ended(), Some(false));
+        assert_eq!(session.step(2, 2), None);
+        clock.advance(Duration::from_secs(1));
+
+        // Undoing the losing move resumes the game, and its clock from where it stopped
+        let outcome = session.undo().unwrap();
+        assert_eq!((outcome.previous_state, outcome.state), (GameState::Stopped(false), GameState::Running));
+        assert_eq!(outcome.ended(), None);
+        assert_eq!(session.undos_used(), 1);
+        assert_eq!(session.elapsed(), Duration::ZERO);
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(session.elapsed(), Duration::from_millis(500));
+
+        let outcome = session.undo().unwrap();
+        assert_eq!(outcome.flags_change, -1);
+        assert_eq!(session.placed_flags(), 0);
+
+        // Redoing it loses the game again
+        session.redo().unwrap();
+        assert_eq!(session.redo().unwrap().ended(), Some(false));
+        assert_eq!(session.redo(), None);
+    }
+
+    #[test]
+    fn wasted_clicks() {
+        let mut session = GameSession::new(minefield());
+        session.step(0, 0).unwrap();
+        let efficiency = session.metrics().unwrap().efficiency().unwrap();
+
+        // Stepping on a revealed spot changes nothing, but still counts against the efficiency
+        assert_eq!(session.step(0, 0), None);
+        assert_eq!(session.clicks().left, 2);
+        assert!(session.metrics().unwrap().efficiency().unwrap() < efficiency);
+
+        // Clicks off the board, or once the game is over, are not counted
+        assert_eq!(session.step(5, 5), None);
+        session.step(0, 3).unwrap();
+        assert_eq!(session.step(2, 3), None);
+        assert_eq!(session.clicks().left, 3);
+    }
+
+    #[test]
+    fn pause() {
+        let clock = FakeClock::default();
+        let mut session = GameSession::new(minefield()).with_clock(clock.clone());
+
+        // Only a running game can be paused
+        assert!(!session.pause());
+        session.step(1, 2).unwrap();
+        session.flag(0, 3).unwrap();
+        clock.advance(Duration::from_millis(1_250));
+        assert!(session.pause());
+        assert!(!session.pause());
+        assert!(session.is_paused());
+
+        // A paused game takes no moves, and doesn't count the time
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(session.step(0, 0), None);
+        assert_eq!(session.flag(2, 0), None);
+        assert!(!session.can_undo());
+        assert_eq!(session.undo(), None);
+        assert_eq!(session.elapsed(), Duration::from_millis(1_250));
+
+        assert!(session.resume());
+        assert!(!session.resume());
+        clock.advance(Duration::from_millis(750));
+        assert_eq!(session.elapsed(), Duration::from_secs(2));
+        assert!(session.step(0, 0).is_some());
+        assert!(session.can_undo());
+    }
+
+    #[test]
+    fn started_board() {
+        // A partially played board is running, and a lost one is over
+        let mut minefield = minefield();
+        minefield.step(0, 0);
+        assert_eq!(GameSession::new(minefield.clone()).state(), GameState::Running);
+
+        minefield.step(0, 3);
+        let session = GameSession::new(minefield);
+        assert_eq!(session.state(), GameState::Stopped(false));
+        assert!(!session.is_playing());
+    }
+}
@@ -0,0 +1,1040 @@
+use crate::minefield;
+
+use minefield::{Minefield, SpotKind, SpotState, StepResult};
+use rand::{seq::index, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::ops::RangeInclusive;
+
+/// Maximum number of steps spent enumerating the mine layouts of the frontier, in a single call to `solve()` or
+/// `probabilities()`
+const ENUMERATION_BUDGET: u32 = 1_000_000;
+
+/// Number of moves of the Monte Carlo sampler, used when the frontier is too large to be enumerated
+const MONTE_CARLO_STEPS: u32 = 100_000;
+
+/// Number of Monte Carlo moves made before sampling, so that the estimates don't depend on the layout the sampler
+/// starts from
+const MONTE_CARLO_BURN_IN: u32 = 10_000;
+
+/// Number of Monte Carlo moves between two samples
+const MONTE_CARLO_SAMPLE_INTERVAL: u32 = 10;
+
+/// Hidden spots whose contents can be deduced from what the player can see in a minefield
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Solution {
+    /// Coordinates of the hidden spots which are certainly not mines
+    pub safe: BTreeSet<(u16, u16)>,
+
+    /// Coordinates of the hidden spots which are certainly mines
+    pub mines: BTreeSet<(u16, u16)>,
+}
+
+impl Solution {
+    /// Check if nothing could be deduced
+    pub fn is_empty(&self) -> bool {
+        self.safe.is_empty() && self.mines.is_empty()
+    }
+
+    /// Record the deductions which follow from knowing that exactly `mines` of the given `spots` are mines
+    fn deduce(&mut self, spots: &BTreeSet<(u16, u16)>, mines: i32) {
+        if spots.is_empty() {
+            return;
+        }
+
+        if mines == 0 {
+            self.safe.extend(spots.iter().copied());
+        } else if mines == spots.len() as i32 {
+            self.mines.extend(spots.iter().copied());
+        }
+    }
+}
+
+/// Deduce which hidden spots are certainly safe, and which are certainly mines.
+///
+/// Only the information available to the player is used: the numbers on revealed spots, the flags (which are trusted
+/// to be correct) and the total number of mines. The hidden contents of the minefield are never looked at.
+///
+/// Single spot and subset rules are applied first, and are then completed by enumerating every mine layout of the
+/// frontier (the hidden spots neighboring revealed ones) which agrees with the revealed numbers and with the number
+/// of mines left. Frontier sections too large to be enumerated within the solver's budget only benefit from the rules.
+pub fn solve(minefield: &Minefield) -> Solution {
+    let view = View::new(minefield);
+
+    let mut solution = view.rules();
+    let enumerated = view.enumerate();
+    solution.safe.extend(enumerated.safe);
+    solution.mines.extend(enumerated.mines);
+
+    solution
+}
+
+/// The probability of each hidden spot of a minefield being a mine
+#[derive(Clone, Debug, PartialEq)]
+pub struct Probabilities {
+    width: u16,
+    values: Vec<Option<f64>>,
+}
+
+impl Probabilities {
+    /// The probability of the spot at the given coordinates being a mine, or `None` if the spot is not hidden (or is
+    /// outside the minefield)
+    pub fn get(&self, x: u16, y: u16) -> Option<f64> {
+        if x < self.width {
+            self.values.get(y as usize * self.width as usize + x as usize).copied().flatten()
+        } else {
+            None
+        }
+    }
+
+    /// The coordinates and probability of the hidden spot least likely to be a mine, if there are any hidden spots
+    pub fn safest(&self) -> Option<((u16, u16), f64)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(index, probability)| probability.map(|probability| (index, probability)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, probability)| {
+                let width = self.width as usize;
+                (((index % width) as u16, (index / width) as u16), probability)
+            })
+    }
+}
+
+/// Compute the probability of each hidden spot being a mine, as seen by the player.
+///
+/// The probabilities are exact, taking into account both the revealed numbers along the frontier and the number of
+/// mines left (flags are trusted to be correct). If the frontier is too large to be enumerated, they are estimated by
+/// Monte Carlo sampling of the mine layouts instead, seeded with the given `seed` (e.g. the seed of the game). If the
+/// view is inconsistent (e.g. because of wrong flags), every hidden spot gets the average density of the mines left.
+pub fn probabilities(minefield: &Minefield, seed: u64) -> Probabilities {
+    let view = View::new(minefield);
+
+    let width = minefield.width();
+    let mut values = vec![None; width as usize * minefield.height() as usize];
+
+    for ((x, y), probability) in view.probabilities(seed) {
+        values[y as usize * width as usize + x as usize] = Some(probability);
+    }
+
+    Probabilities { width, values }
+}
+
+/// Check if the minefield can be cleared by logical deduction alone, starting with a step at the given coordinates
+pub(crate) fn clears_without_guessing(minefield: &Minefield, x: u16, y: u16) -> bool {
+    let mut minefield = minefield.clone();
+
+    if minefield.step(x, y) != StepResult::Phew {
+        return false;
+    }
+
+    while !minefield.is_cleared() {
+        // The rules are much cheaper than the enumeration, and are enough most of the time
+        let view = View::new(&minefield);
+        let mut solution = view.rules();
+        if solution.is_empty() {
+            solution = view.enumerate();
+        }
+
+        if solution.is_empty() {
+            // Stuck: the player would have to guess
+            return false;
+        }
+
+        for (x, y) in solution.mines {
+            minefield.toggle_flag(x, y);
+        }
+
+        for (x, y) in solution.safe {
+            if minefield.step(x, y) != StepResult::Phew {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+/// Exactly `mines` of the hidden `spots` neighboring a revealed spot are mines
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Constraint {
+    spots: BTreeSet<(u16, u16)>,
+    mines: i32,
+}
+
+/// What the player can see of a minefield
+struct View {
+    /// Constraints given by each revealed spot which still has hidden neighbors, keyed by its coordinates
+    constraints: BTreeMap<(u16, u16), Constraint>,
+
+    /// Coordinates of all the hidden spots
+    hidden: BTreeSet<(u16, u16)>,
+
+    /// Number of mines which have not been flagged (or exploded) yet
+    mines_left: i32,
+}
+
+impl View {
+    fn new(minefield: &Minefield) -> Self {
+        let mut constraints = BTreeMap::new();
+        let mut hidden = BTreeSet::new();
+        let mut mines_left = minefield.mines() as i32;
+
+        for (x, y) in coords(minefield) {
+            let spot = minefield.spot(x, y).unwrap();
+
+            match (spot.state(), spot.kind()) {
+                (SpotState::Hidden, _) => {
+                    hidden.insert((x, y));
+                },
+                (SpotState::Flagged | SpotState::Exploded, _) => {
+                    mines_left -= 1;
+                },
+                (SpotState::Revealed, SpotKind::Empty(n)) => {
+                    let mut spots = BTreeSet::new();
+                    let mut mines = n;
+
+                    for (nx, ny) in minefield.neighbors(x, y) {
+                        match minefield.spot(nx, ny).unwrap().state() {
+                            SpotState::Hidden => {
+                                spots.insert((nx, ny));
+                            },
+                            SpotState::Flagged | SpotState::Exploded => {
+                                mines -= 1;
+                            },
+                            SpotState::Revealed => {},
+                        }
+                    }
+
+                    if !spots.is_empty() {
+                        constraints.insert((x, y), Constraint { spots, mines });
+                    }
+                },
+                (SpotState::Revealed, SpotKind::Mine) => {
+                    // A revealed mine is `Exploded`, never `Revealed`
+                    unreachable!()
+                },
+            }
+        }
+
+        Self { constraints, hidden, mines_left }
+    }
+
+    /// Apply the single spot, subset and global mine count rules
+    fn rules(&self) -> Solution {
+        let mut solution = Solution::default();
+
+        // Single spot rules: a revealed number is either satisfied by its flags, or needs all its hidden neighbors
+        for constraint in self.constraints.values() {
+            solution.deduce(&constraint.spots, constraint.mines);
+        }
+
+        // Subset rules: if the hidden neighbors of `a` are all neighbors of `b`, then the remaining neighbors of `b`
+        // hold exactly `b.mines - a.mines` mines. Only revealed spots at most 2 spots apart can share hidden neighbors.
+        for (&(ax, ay), a) in &self.constraints {
+            for bx in ax.saturating_sub(2)..=ax.saturating_add(2) {
+                for by in ay.saturating_sub(2)..=ay.saturating_add(2) {
+                    if let Some(b) = self.constraints.get(&(bx, by)) {
+                        if (bx, by) != (ax, ay) && a.spots.is_subset(&b.spots) {
+                            let rest: BTreeSet<(u16, u16)> = b.spots.difference(&a.spots).copied().collect();
+                            solution.deduce(&rest, b.mines - a.mines);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Global rule: the number of mines left to be found may account for all (or none) of the hidden spots
+        solution.deduce(&self.hidden, self.mines_left);
+
+        solution
+    }
+
+    /// Split the frontier into sections which don't share any constraints
+    fn sections(&self) -> Vec<Section> {
+        let mut spot_constraints: BTreeMap<(u16, u16), Vec<(u16, u16)>> = BTreeMap::new();
+        for (&source, constraint) in &self.constraints {
+            for &spot in &constraint.spots {
+                spot_constraints.entry(spot).or_default().push(source);
+            }
+        }
+
+        let mut visited_spots = BTreeSet::new();
+        let mut visited_constraints = BTreeSet::new();
+        let mut sections = vec![];
+
+        for &start in spot_constraints.keys() {
+            if !visited_spots.insert(start) {
+                continue;
+            }
+
+            // Breadth first, so that spots sharing constraints are enumerated one after the other
+            let mut spots = vec![];
+            let mut sources = vec![];
+            let mut to_visit = VecDeque::from([start]);
+
+            while let Some(spot) = to_visit.pop_front() {
+                spots.push(spot);
+
+                for &source in &spot_constraints[&spot] {
+                    if visited_constraints.insert(source) {
+                        sources.push(source);
+
+                        for &next in &self.constraints[&source].spots {
+                            if visited_spots.insert(next) {
+                                to_visit.push_back(next);
+                            }
+                        }
+                    }
+                }
+            }
+
+            let spot_index: HashMap<(u16, u16), usize> = spots.iter().enumerate().map(|(i, &spot)| (spot, i)).collect();
+            let constraints = sources
+                .iter()
+                .map(|source| {
+                    let constraint = &self.constraints[source];
+                    (constraint.spots.iter().map(|spot| spot_index[spot]).collect(), constraint.mines)
+                })
+                .collect();
+
+            sections.push(Section { spots, constraints });
+        }
+
+        sections
+    }
+
+    /// The hidden spots which are not part of any frontier section
+    fn interior(&self, sections: &[Section]) -> Vec<(u16, u16)> {
+        let frontier: BTreeSet<(u16, u16)> = sections.iter().flat_map(|section| section.spots.iter().copied()).collect();
+
+        self.hidden.difference(&frontier).copied().collect()
+    }
+
+    /// Compute the probability of each hidden spot being a mine, sampling with the given `seed` if needed
+    fn probabilities(&self, seed: u64) -> BTreeMap<(u16, u16), f64> {
+        let sections = self.sections();
+        let interior = self.interior(&sections);
+
+        let mut budget = ENUMERATION_BUDGET;
+        let layouts: Option<Vec<SectionLayouts>> = sections.iter().map(|section| section.layouts(&mut budget)).collect();
+
+        let probabilities = match layouts {
+            Some(layouts) => self.exact_probabilities(&sections, &layouts, &interior),
+            None => self.sampled_probabilities(&sections, &interior, seed),
+        };
+
+        probabilities.unwrap_or_else(|| {
+            let density = if self.hidden.is_empty() {
+                0.0
+            } else {
+                self.mines_left.clamp(0, self.hidden.len() as i32) as f64 / self.hidden.len() as f64
+            };
+
+            self.hidden.iter().map(|spot| (*spot, density)).collect()
+        })
+    }
+
+    /// Weigh every combination of section layouts by the number of ways the remaining mines can be spread over the
+    /// interior. Returns `None` if no combination agrees with the number of mines left.
+    fn exact_probabilities(
+        &self,
+        sections: &[Section],
+        layouts: &[SectionLayouts],
+        interior: &[(u16, u16)],
+    ) -> Option<BTreeMap<(u16, u16), f64>> {
+        if self.mines_left < 0 {
+            return None;
+        }
+        let mines_left = self.mines_left as usize;
+
+        // Number of layouts of each section, as a polynomial in the number of mines
+        let polynomials: Vec<Vec<f64>> = layouts
+            .iter()
+            .map(|layouts| {
+                let mut polynomial = vec![0.0; layouts.by_mines.keys().last().map_or(0, |max| max + 1)];
+                for (&mines, (count, _)) in &layouts.by_mines {
+                    polynomial[mines] = *count as f64;
+                }
+                polynomial
+            })
+            .collect();
+
+        // Relative number of ways to place the rest of the mines in the interior, for each number of frontier mines
+        let frontier_len: usize = sections.iter().map(|section| section.spots.len()).sum();
+        let interior_weights = interior_weights(frontier_len, interior.len(), mines_left);
+
+        let product = polynomials.iter().fold(vec![1.0], |product, polynomial| multiply(&product, polynomial));
+        let total_weight: f64 = product.iter().zip(interior_weights.iter()).map(|(count, weight)| count * weight).sum();
+
+        if total_weight <= 0.0 {
+            return None;
+        }
+
+        let mut probabilities = BTreeMap::new();
+
+        for (i, (section, layouts)) in sections.iter().zip(layouts.iter()).enumerate() {
+            let others = polynomials
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .fold(vec![1.0], |product, (_, polynomial)| multiply(&product, polynomial));
+
+            let mut spot_weights = vec![0.0; section.spots.len()];
+            for (&mines, (_, spot_mines)) in &layouts.by_mines {
+                let weight: f64 = others
+                    .iter()
+                    .enumerate()
+                    .map(|(other_mines, count)| count * interior_weights.get(mines + other_mines).copied().unwrap_or(0.0))
+                    .sum();
+
+                for (spot_weight, &spot_mines) in spot_weights.iter_mut().zip(spot_mines.iter()) {
+                    *spot_weight += spot_mines as f64 * weight;
+                }
+            }
+
+            for (spot, spot_weight) in section.spots.iter().zip(spot_weights) {
+                probabilities.insert(*spot, spot_weight / total_weight);
+            }
+        }
+
+        if !interior.is_empty() {
+            let interior_mines: f64 = product
+                .iter()
+                .zip(interior_weights.iter())
+                .enumerate()
+                .map(|(frontier_mines, (count, weight))| {
+                    count * weight * mines_left.saturating_sub(frontier_mines) as f64
+                })
+                .sum();
+
+            let probability = interior_mines / total_weight / interior.len() as f64;
+            probabilities.extend(interior.iter().map(|spot| (*spot, probability)));
+        }
+
+        Some(probabilities)
+    }
+
+    /// Estimate the probabilities by sampling mine layouts of the whole hidden area (frontier and interior), with a
+    /// Markov chain which swaps some mines with as many safe spots whenever the swap agrees with the revealed numbers.
+    /// Returns `None` if no layout agreeing with the view could be found to start from.
+    fn sampled_probabilities(
+        &self,
+        sections: &[Section],
+        interior: &[(u16, u16)],
+        seed: u64,
+    ) -> Option<BTreeMap<(u16, u16), f64>> {
+        if self.mines_left < 0 {
+            return None;
+        }
+        let mines_left = self.mines_left as usize;
+
+        let frontier = Section::merge(sections);
+        let min_frontier_mines = mines_left.saturating_sub(interior.len());
+        let mut budget = ENUMERATION_BUDGET;
+        let initial = frontier.first_layout(min_frontier_mines..=mines_left, &mut budget)?;
+
+        // The frontier spots come first, followed by the interior spots, which don't take part in any constraint
+        let spots: Vec<(u16, u16)> = frontier.spots.iter().chain(interior.iter()).copied().collect();
+        let interior_mines = mines_left - initial.iter().filter(|is_mine| **is_mine).count();
+        let is_mine: Vec<bool> = initial
+            .into_iter()
+            .chain((0..interior.len()).map(|i| i < interior_mines))
+            .collect();
+
+        let mut spot_constraints = vec![vec![]; spots.len()];
+        let mut placed = vec![0; frontier.constraints.len()];
+        for (c, (constraint_spots, _)) in frontier.constraints.iter().enumerate() {
+            for &spot in constraint_spots {
+                spot_constraints[spot].push(c);
+                placed[c] += is_mine[spot] as i32;
+            }
+        }
+
+        let mut mines: Vec<usize> = (0..spots.len()).filter(|spot| is_mine[*spot]).collect();
+        let mut safe: Vec<usize> = (0..spots.len()).filter(|spot| !is_mine[*spot]).collect();
+        let mut mine_samples = vec![0u32; spots.len()];
+        let mut samples = 0u32;
+
+        // Most moves swap a single mine, but any number of them can be swapped at once, so that every layout can be
+        // reached, even when no single swap agrees with the revealed numbers
+        let max_swapped = mines.len().min(safe.len());
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        for step in 0..MONTE_CARLO_BURN_IN + MONTE_CARLO_STEPS {
+            if max_swapped > 0 {
+                let mut swapped = 1;
+                while swapped < max_swapped && rng.gen_bool(0.5) {
+                    swapped += 1;
+                }
+                let mine_is = index::sample(&mut rng, mines.len(), swapped);
+                let safe_is = index::sample(&mut rng, safe.len(), swapped);
+
+                for (mine_i, safe_i) in mine_is.iter().zip(safe_is.iter()) {
+                    for &c in &spot_constraints[mines[mine_i]] {
+                        placed[c] -= 1;
+                    }
+                    for &c in &spot_constraints[safe[safe_i]] {
+                        placed[c] += 1;
+                    }
+                }
+
+                let is_consistent = mine_is
+                    .iter()
+                    .map(|i| mines[i])
+                    .chain(safe_is.iter().map(|i| safe[i]))
+                    .all(|spot| spot_constraints[spot].iter().all(|&c| placed[c] == frontier.constraints[c].1));
+
+                for (mine_i, safe_i) in mine_is.iter().zip(safe_is.iter()) {
+                    if is_consistent {
+                        std::mem::swap(&mut mines[mine_i], &mut safe[safe_i]);
+                    } else {
+                        for &c in &spot_constraints[mines[mine_i]] {
+                            placed[c] += 1;
+                        }
+                        for &c in &spot_constraints[safe[safe_i]] {
+                            placed[c] -= 1;
+                        }
+                    }
+                }
+            }
+
+            if step >= MONTE_CARLO_BURN_IN && step % MONTE_CARLO_SAMPLE_INTERVAL == 0 {
+                samples += 1;
+                for &mine in &mines {
+                    mine_samples[mine] += 1;
+                }
+            }
+        }
+
+        Some(spots.into_iter().zip(mine_samples).map(|(spot, count)| (spot, count as f64 / samples as f64)).collect())
+    }
+
+    /// Enumerate the mine layouts of every frontier section, and keep the spots which are mines (or safe) in all the
+    /// layouts which also agree with the number of mines left
+    fn enumerate(&self) -> Solution {
+        let mut solution = Solution::default();
+
+        let sections = self.sections();
+        let mut budget = ENUMERATION_BUDGET;
+        let layouts: Vec<Option<SectionLayouts>> = sections.iter().map(|section| section.layouts(&mut budget)).collect();
+
+        // Sections which could not be enumerated may hold any number of mines
+        let counts: Vec<BTreeSet<usize>> = sections
+            .iter()
+            .zip(layouts.iter())
+            .map(|(section, layouts)| match layouts {
+                Some(layouts) => layouts.by_mines.keys().copied().collect(),
+                None => (0..=section.spots.len()).collect(),
+            })
+            .collect();
+
+        let interior = self.interior(&sections);
+
+        // The mines left which are not on the frontier must fit in the interior
+        let is_feasible_total = |total: usize| {
+            total as i32 <= self.mines_left && self.mines_left - total as i32 <= interior.len() as i32
+        };
+
+        for (i, (section, layouts)) in sections.iter().zip(layouts.iter()).enumerate() {
+            if let Some(layouts) = layouts {
+                let other_totals = sums(counts.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, c)| c));
+                let feasible: Vec<&(u64, Vec<u64>)> = layouts.by_mines
+                    .iter()
+                    .filter(|(mines, _)| other_totals.iter().any(|total| is_feasible_total(*mines + total)))
+                    .map(|(_, layouts)| layouts)
+                    .collect();
+
+                // No feasible layouts means the view is inconsistent (e.g. wrong flags), so nothing can be deduced
+                if feasible.is_empty() {
+                    continue;
+                }
+
+                for (spot_i, spot) in section.spots.iter().enumerate() {
+                    if feasible.iter().all(|(_, spot_mines)| spot_mines[spot_i] == 0) {
+                        solution.safe.insert(*spot);
+                    } else if feasible.iter().all(|(count, spot_mines)| spot_mines[spot_i] == *count) {
+                        solution.mines.insert(*spot);
+                    }
+                }
+            }
+        }
+
+        if !interior.is_empty() {
+            let totals: Vec<usize> = sums(counts.iter()).into_iter().filter(|total| is_feasible_total(*total)).collect();
+            let interior: BTreeSet<(u16, u16)> = interior.into_iter().collect();
+
+            if !totals.is_empty() && totals.iter().all(|total| self.mines_left == *total as i32) {
+                solution.safe.extend(interior);
+            } else if !totals.is_empty()
+                && totals.iter().all(|total| self.mines_left - *total as i32 == interior.len() as i32)
+            {
+                solution.mines.extend(interior);
+            }
+        }
+
+        solution
+    }
+}
+
+/// A connected section of the frontier: hidden spots neighboring revealed spots, linked by shared constraints
+struct Section {
+    spots: Vec<(u16, u16)>,
+
+    /// Constraints over the section, as the indices of their `spots` and the number of mines among them
+    constraints: Vec<(Vec<usize>, i32)>,
+}
+
+/// All the mine layouts of a frontier section, grouped by their number of mines
+struct SectionLayouts {
+    /// For each possible number of mines: how many layouts have that many mines, and how many of those layouts have a
+    /// mine on each spot of the section
+    by_mines: BTreeMap<usize, (u64, Vec<u64>)>,
+}
+
+impl Section {
+    /// Join several sections into one
+    fn merge(sections: &[Section]) -> Section {
+        let mut merged = Section { spots: vec![], constraints: vec![] };
+
+        for section in sections {
+            let offset = merged.spots.len();
+            merged.spots.extend(section.spots.iter().copied());
+            merged.constraints.extend(
+                section.constraints
+                    .iter()
+                    .map(|(spots, mines)| (spots.iter().map(|spot| spot + offset).collect(), *mines))
+            );
+        }
+
+        merged
+    }
+
+    /// Enumerate all the mine layouts agreeing with the section's constraints, or `None` if the budget runs out
+    fn layouts(&self, budget: &mut u32) -> Option<SectionLayouts> {
+        let mut enumerator = Enumerator::new(self, 0..=self.spots.len(), false, budget);
+
+        if enumerator.visit(0, 0) {
+            Some(SectionLayouts { by_mines: enumerator.by_mines })
+        } else {
+            None
+        }
+    }
+
+    /// Find one mine layout agreeing with the section's constraints, with a number of mines in the given range
+    fn first_layout(&self, mines: RangeInclusive<usize>, budget: &mut u32) -> Option<Vec<bool>> {
+        let mut enumerator = Enumerator::new(self, mines, true, budget);
+        enumerator.visit(0, 0);
+
+        enumerator.first
+    }
+}
+
+/// Depth first enumeration of the mine layouts of a frontier section
+struct Enumerator<'a> {
+    section: &'a Section,
+
+    /// Indices of the constraints each spot takes part in
+    spot_constraints: Vec<Vec<usize>>,
+
+    /// Number of mines placed so far, for each constraint
+    placed: Vec<i32>,
+
+    /// Number of spots without a decision yet, for each constraint
+    open: Vec<i32>,
+
+    /// The layout being built
+    layout: Vec<bool>,
+
+    /// Only layouts with a number of mines in this range are kept
+    mines: RangeInclusive<usize>,
+
+    by_mines: BTreeMap<usize, (u64, Vec<u64>)>,
+
+    /// Stop at the first layout found, and keep it here
+    first_only: bool,
+    first: Option<Vec<bool>>,
+
+    budget: &'a mut u32,
+}
+
+impl<'a> Enumerator<'a> {
+    fn new(section: &'a Section, mines: RangeInclusive<usize>, first_only: bool, budget: &'a mut u32) -> Self {
+        let mut spot_constraints = vec![vec![]; section.spots.len()];
+        for (c, (spots, _)) in section.constraints.iter().enumerate() {
+            for &spot in spots {
+                spot_constraints[spot].push(c);
+            }
+        }
+
+        Self {
+            section,
+            spot_constraints,
+            placed: vec![0; section.constraints.len()],
+            open: section.constraints.iter().map(|(spots, _)| spots.len() as i32).collect(),
+            layout: vec![false; section.spots.len()],
+            mines,
+            by_mines: BTreeMap::new(),
+            first_only,
+            first: None,
+            budget,
+        }
+    }
+
+    /// Decide the contents of `spot` and of all the spots after it. Returns `false` if the enumeration must stop,
+    /// because the budget ran out (or because the first layout was found, if that's all that's needed).
+    fn visit(&mut self, spot: usize, mines: usize) -> bool {
+        if *self.budget == 0 {
+            return false;
+        }
+        *self.budget -= 1;
+
+        if mines > *self.mines.end() {
+            return true;
+        }
+
+        if spot == self.layout.len() {
+            if !self.mines.contains(&mines) {
+                return true;
+            }
+
+            if self.first_only {
+                self.first = Some(self.layout.clone());
+                return false;
+            }
+
+            let spot_count = self.layout.len();
+            let (count, spot_mines) = self.by_mines.entry(mines).or_insert_with(|| (0, vec![0; spot_count]));
+            *count += 1;
+            for (spot_mines, &is_mine) in spot_mines.iter_mut().zip(self.layout.iter()) {
+                *spot_mines += is_mine as u64;
+            }
+
+            return true;
+        }
+
+        for is_mine in [false, true] {
+            let is_consistent = self.assign(spot, is_mine);
+            let is_done = !is_consistent || self.visit(spot + 1, mines + is_mine as usize);
+            self.unassign(spot, is_mine);
+
+            if !is_done {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Decide the contents of `spot`, and check that all its constraints can still be satisfied
+    fn assign(&mut self, spot: usize, is_mine: bool) -> bool {
+        self.layout[spot] = is_mine;
+
+        let mut is_consistent = true;
+        for &c in &self.spot_constraints[spot] {
+            self.open[c] -= 1;
+            self.placed[c] += is_mine as i32;
+
+            let mines = self.section.constraints[c].1;
+            if self.placed[c] > mines || self.placed[c] + self.open[c] < mines {
+                is_consistent = false;
+            }
+        }
+
+        is_consistent
+    }
+
+    /// Undo `assign()`
+    fn unassign(&mut self, spot: usize, is_mine: bool) {
+        for &c in &self.spot_constraints[spot] {
+            self.open[c] += 1;
+            self.placed[c] -= is_mine as i32;
+        }
+    }
+}
+
+/// Multiply two polynomials, given by their coefficients
+fn multiply(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut product = vec![0.0; (a.len() + b.len()).saturating_sub(1)];
+
+    for (i, a) in a.iter().enumerate() {
+        for (j, b) in b.iter().enumerate() {
+            product[i + j] += a * b;
+        }
+    }
+
+    product
+}
+
+/// For each number of frontier mines (up to `frontier_len`), the number of ways to place the rest of the `mines_left`
+/// mines in the interior, scaled so that the largest is `1.0` (the actual numbers easily overflow a `f64`)
+fn interior_weights(frontier_len: usize, interior_len: usize, mines_left: usize) -> Vec<f64> {
+    let ln_weights: Vec<Option<f64>> = (0..=frontier_len)
+        .map(|frontier_mines| {
+            mines_left
+                .checked_sub(frontier_mines)
+                .filter(|interior_mines| *interior_mines <= interior_len)
+                .map(|interior_mines| ln_binomial(interior_len, interior_mines))
+        })
+        .collect();
+
+    let ln_max = ln_weights.iter().flatten().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    ln_weights.into_iter().map(|ln_weight| ln_weight.map_or(0.0, |ln_weight| (ln_weight - ln_max).exp())).collect()
+}
+
+/// Natural logarithm of the binomial coefficient `n` choose `k`
+fn ln_binomial(n: usize, k: usize) -> f64 {
+    let k = k.min(n - k);
+
+    (1..=k).map(|i| ((n - k + i) as f64 / i as f64).ln()).sum()
+}
+
+/// All the totals obtainable by picking one number from each of the given sets
+fn sums<'a>(sets: impl Iterator<Item = &'a BTreeSet<usize>>) -> BTreeSet<usize> {
+    let mut totals = BTreeSet::from([0]);
+
+    for set in sets {
+        totals = totals.iter().flat_map(|total| set.iter().map(move |n| total + n)).collect();
+    }
+
+    totals
+}
+
+/// Iterate over the coordinates of all the spots in the minefield
+fn coords(minefield: &Minefield) -> impl Iterator<Item = (u16, u16)> {
+    let width = minefield.width();
+    let height = minefield.height();
+
+    (0..height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_spot_rules() {
+        // A single mine in the corner
+        //     0 1 2
+        // 0 [ ☢ 1   ]
+        // 1 [ 1 1   ]
+        // 2 [       ]
+        let mut minefield = Minefield::new(3, 3).with_mines_at(&[(0, 0)]);
+
+        // Flood reveal everything but the mine
+        //     0 1 2
+        // 0 [ • 1   ]
+        // 1 [ 1 1   ]
+        // 2 [       ]
+        assert_eq!(minefield.step(2, 2), StepResult::Phew);
+
+        let solution = solve(&minefield);
+        assert!(solution.safe.is_empty());
+        assert_eq!(solution.mines, BTreeSet::from([(0, 0)]));
+
+        // Once flagged, the mine is no longer part of any deduction
+        minefield.toggle_flag(0, 0);
+        assert!(solve(&minefield).is_empty());
+    }
+
+    #[test]
+    fn flags_satisfy_numbers() {
+        //     0 1 2
+        // 0 [ ☢ 1   ]
+        // 1 [ 1 1   ]
+        let mut minefield = Minefield::new(3, 2).with_mines_at(&[(0, 0)]);
+
+        // Reveal a single number, and flag the mine
+        //     0 1 2
+        // 0 [ ⚐ 1 • ]
+        // 1 [ • • • ]
+        assert_eq!(minefield.step(1, 0), StepResult::Phew);
+        minefield.toggle_flag(0, 0);
+
+        let rules = View::new(&minefield).rules();
+        assert_eq!(rules.safe, BTreeSet::from([(2, 0), (0, 1), (1, 1), (2, 1)]));
+        assert!(rules.mines.is_empty());
+    }
+
+    #[test]
+    fn subset_rules() {
+        // The 1-2-1 pattern
+        //     0 1 2 3 4
+        // 0 [ 1 ☢ 2 ☢ 1 ]
+        // 1 [ 1 1 2 1 1 ]
+        let mut minefield = Minefield::new(5, 2).with_mines_at(&[(1, 0), (3, 0)]);
+
+        //     0 1 2 3 4
+        // 0 [ • • • • • ]
+        // 1 [ 1 1 2 1 1 ]
+        for x in 0..5 {
+            assert_eq!(minefield.step(x, 1), StepResult::Phew);
+        }
+
+        // The rules alone find the safe spot in the middle
+        let rules = View::new(&minefield).rules();
+        assert_eq!(rules.safe, BTreeSet::from([(2, 0)]));
+        assert!(rules.mines.is_empty());
+
+        // The enumeration solves the whole pattern
+        let solution = solve(&minefield);
+        assert_eq!(solution.safe, BTreeSet::from([(0, 0), (2, 0), (4, 0)]));
+        assert_eq!(solution.mines, BTreeSet::from([(1, 0), (3, 0)]));
+    }
+
+    #[test]
+    fn global_mine_count() {
+        // A wall of mines, with an unreachable interior behind it
+        //     0 1 2 3 4
+        // 0 [   2 ☢ 2   ]
+        // 1 [   3 ☢ 3   ]
+        // 2 [   2 ☢ 2   ]
+        let mut minefield = Minefield::new(5, 3).with_mines_at(&[(2, 0), (2, 1), (2, 2)]);
+
+        //     0 1 2 3 4
+        // 0 [   2 • • • ]
+        // 1 [   3 • • • ]
+        // 2 [   2 • • • ]
+        assert_eq!(minefield.step(0, 0), StepResult::Phew);
+
+        // The wall takes all the mines, so the interior must be safe
+        let solution = solve(&minefield);
+        assert_eq!(solution.mines, BTreeSet::from([(2, 0), (2, 1), (2, 2)]));
+        assert_eq!(solution.safe, BTreeSet::from([(3, 0), (3, 1), (3, 2), (4, 0), (4, 1), (4, 2)]));
+    }
+
+    #[test]
+    fn probabilities_without_frontier() {
+        // Nothing revealed yet: every spot is equally likely to be a mine
+        let minefield = Minefield::new(10, 10).with_mines_seeded(10, 0);
+        let mine_probabilities = probabilities(&minefield, 0);
+
+        for y in 0..10 {
+            for x in 0..10 {
+                assert!((mine_probabilities.get(x, y).unwrap() - 0.1).abs() < 1e-9);
+            }
+        }
+        assert_eq!(mine_probabilities.get(10, 0), None);
+    }
+
+    #[test]
+    fn probabilities_of_patterns() {
+        // The 1-2-1 pattern is fully determined
+        let mut minefield = Minefield::new(5, 2).with_mines_at(&[(1, 0), (3, 0)]);
+        for x in 0..5 {
+            minefield.step(x, 1);
+        }
+
+        let mine_probabilities = probabilities(&minefield, 0);
+        for (x, expected) in [(0, 0.0), (1, 1.0), (2, 0.0), (3, 1.0), (4, 0.0)] {
+            assert!((mine_probabilities.get(x, 0).unwrap() - expected).abs() < 1e-9);
+            assert_eq!(mine_probabilities.get(x, 1), None);
+        }
+        assert_eq!(mine_probabilities.safest().map(|(_, probability)| probability), Some(0.0));
+
+        // A 50/50 is a 50/50
+        let mut minefield = Minefield::new(3, 2).with_mines_at(&[(2, 0)]);
+        minefield.step(0, 0);
+
+        let mine_probabilities = probabilities(&minefield, 0);
+        assert!((mine_probabilities.get(2, 0).unwrap() - 0.5).abs() < 1e-9);
+        assert!((mine_probabilities.get(2, 1).unwrap() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn probabilities_add_up_to_mines_left() {
+        // A game in progress, with both a frontier and an interior
+        let mut minefield = Minefield::new(16, 16).with_mines_seeded(40, 1);
+        let (x, y) = (0..16)
+            .flat_map(|y| (0..16).map(move |x| (x, y)))
+            .find(|(x, y)| minefield.spot(*x, *y).unwrap().kind() == SpotKind::Empty(0))
+            .unwrap();
+        minefield.step(x, y);
+
+        let view = View::new(&minefield);
+        let probabilities = view.probabilities(0);
+        let total: f64 = probabilities.values().sum();
+        assert_eq!(probabilities.len(), view.hidden.len());
+        assert!((total - view.mines_left as f64).abs() < 1e-6);
+
+        // Certain deductions have certain probabilities
+        let solution = solve(&minefield);
+        for spot in &solution.safe {
+            assert!(probabilities[spot].abs() < 1e-9);
+        }
+        for spot in &solution.mines {
+            assert!((probabilities[spot] - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn sampled_probabilities() {
+        // The Monte Carlo estimates are close to the exact probabilities
+        let mut minefield = Minefield::new(5, 3).with_mines_at(&[(2, 0), (4, 2)]);
+        minefield.step(0, 0);
+
+        let view = View::new(&minefield);
+        let sections = view.sections();
+        let interior = view.interior(&sections);
+        let exact = view.probabilities(0);
+        let sampled = view.sampled_probabilities(&sections, &interior, 0).unwrap();
+
+        assert_eq!(exact.len(), sampled.len());
+        for (spot, probability) in exact {
+            assert!((probability - sampled[&spot]).abs() < 0.05, "{:?}: {} vs {}", spot, probability, sampled[&spot]);
+        }
+    }
+
+    #[test]
+    fn sampler_reaches_every_layout() {
+        // The frontier has two layouts, with mines on either `a` and `d`, or `b` and `c`, and no single swap of a mine
+        // leads from one to the other
+        //   a b
+        //   c d
+        let (a, b, c, d) = ((0, 0), (1, 0), (0, 1), (1, 1));
+        let one_of = |spots: [(u16, u16); 2]| Constraint { spots: spots.into_iter().collect(), mines: 1 };
+        let view = View {
+            constraints: BTreeMap::from([
+                ((9, 0), one_of([a, b])),
+                ((9, 1), one_of([c, d])),
+                ((9, 2), one_of([a, c])),
+                ((9, 3), one_of([b, d])),
+            ]),
+            hidden: [a, b, c, d, (5, 5), (6, 5)].into_iter().collect(),
+            mines_left: 3,
+        };
+
+        let sections = view.sections();
+        let interior = view.interior(&sections);
+        let mut budget = ENUMERATION_BUDGET;
+        let layouts: Vec<SectionLayouts> = sections.iter().map(|section| section.layouts(&mut budget).unwrap()).collect();
+        let exact = view.exact_probabilities(&sections, &layouts, &interior).unwrap();
+
+        for seed in [1, 2, 3] {
+            let sampled = view.sampled_probabilities(&sections, &interior, seed).unwrap();
+
+            assert_eq!(exact.len(), sampled.len());
+            for (spot, probability) in &exact {
+                assert!((probability - sampled[spot]).abs() < 0.05, "{:?}: {} vs {}", spot, probability, sampled[spot]);
+            }
+        }
+    }
+
+    #[test]
+    fn no_deduction_on_guess() {
+        // A 50/50 which looks the same whichever of the two spots holds the mine
+        //     0 1 2
+        // 0 [   1 ☢ ]
+        // 1 [   1 1 ]
+        for mine in [(2, 0), (2, 1)] {
+            let mut minefield = Minefield::new(3, 2).with_mines_at(&[mine]);
+
+            //     0 1 2
+            // 0 [   1 • ]
+            // 1 [   1 • ]
+            assert_eq!(minefield.step(0, 0), StepResult::Phew);
+            assert!(solve(&minefield).is_empty());
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use crate::high_scores;
+
+use high_scores::ScoreBoard;
+use serde::{Serialize, Deserialize, Deserializer};
+use std::time::Duration;
+
+/// Lifetime statistics of the games played on a board
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BoardStats {
+    pub board: ScoreBoard,
+    pub played: u32,
+    pub won: u32,
+
+    /// Number of games left unfinished for another one. They count as played (but not won), and end the streak.
+    #[serde(default)]
+    pub abandoned: u32,
+
+    /// Number of finished games in which hints or undos were used. They count as played (and won, if they were), but
+    /// are left out of the streaks and the win times.
+    #[serde(default)]
+    pub assisted: u32,
+
+    /// Number of games won in a row without help, up to the last game
+    pub current_streak: u32,
+    pub longest_streak: u32,
+
+    /// Times of the games won without help, in the order they were won
+    #[serde(deserialize_with = "deserialize_win_times")]
+    pub win_times: Vec<Duration>,
+}
+
+/// Read the times of the won games, which were recorded in whole seconds before they were counted with millisecond
+/// precision
+fn deserialize_win_times<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Duration>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum WinTime {
+        Seconds(u64),
+        Precise(Duration),
+    }
+
+    let win_times = Vec::<WinTime>::deserialize(deserializer)?;
+
+    Ok(win_times
+        .into_iter()
+        .map(|win_time| match win_time {
+            WinTime::Seconds(seconds) => Duration::from_secs(seconds),
+            WinTime::Precise(duration) => duration,
+        })
+        .collect())
+}
+
+/// Number of won games which took from `from` to `to` seconds (inclusive)
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct HistogramBucket {
+    pub from: i32,
+    pub to: i32,
+    pub count: u32,
+}
+
+impl BoardStats {
+    fn new(board: ScoreBoard) -> Self {
+        Self { board, played: 0, won: 0, abandoned: 0, assisted: 0, current_streak: 0, longest_streak: 0, win_times: vec![] }
+    }
+
+    /// Ratio of won games to played games, if any game was played
+    pub fn win_rate(&self) -> Option<f64> {
+        (self.played > 0).then(|| self.won as f64 / self.played as f64)
+    }
+
+    /// Average time of the won games
+    pub fn average_win_time(&self) -> Option<Duration> {
+        if self.win_times.is_empty() {
+            return None;
+        }
+
+        Some(self.win_times.iter().sum::<Duration>() / self.win_times.len() as u32)
+    }
+
+    /// Median time of the won games
+    pub fn median_win_time(&self) -> Option<Duration> {
+        let mut win_times = self.win_times.clone();
+        win_times.sort_unstable();
+
+        let middle = win_times.len() / 2;
+        match win_times.len() {
+            0 => None,
+            len if len % 2 == 1 => Some(win_times[middle]),
+            _ => Some((win_times[middle - 1] + win_times[middle]) / 2),
+        }
+    }
+
+    /// Count the won games by time, in whole seconds, in at most `max_buckets` buckets of equal width, covering the
+    /// fastest to the slowest win
+    pub fn histogram(&self, max_buckets: usize) -> Vec<HistogramBucket> {
+        let seconds: Vec<i32> = self.win_times.iter().map(|win_time| win_time.as_secs() as i32).collect();
+        let (min, max) = match (seconds.iter().min(), seconds.iter().max()) {
+            (Some(&min), Some(&max)) => (min, max),
+            _ => return vec![],
+        };
+
+        let max_buckets = max_buckets.max(1) as i32;
+        let width = ((max - min + 1) + max_buckets - 1) / max_buckets;
+        let bucket_count = (max - min) / width + 1;
+
+        let mut buckets: Vec<HistogramBucket> = (0..bucket_count)
+            .map(|i| HistogramBucket { from: min + i * width, to: min + (i + 1) * width - 1, count: 0 })
+            .collect();
+
+        for seconds in &seconds {
+            buckets[((seconds - min) / width) as usize].count += 1;
+        }
+
+        buckets
+    }
+
+    fn record(&mut self, is_won: bool, elapsed: Duration, assisted: bool) {
+        self.played += 1;
+
+        if is_won {
+            self.won += 1;
+        }
+
+        if assisted {
+            self.assisted += 1;
+        } else if is_won {
+            self.current_streak += 1;
+            self.longest_streak = self.longest_streak.max(self.current_streak);
+            self.win_times.push(elapsed);
+        } else {
+            self.current_streak = 0;
+        }
+    }
+
+    fn record_abandoned(&mut self) {
+        self.played += 1;
+        self.abandoned += 1;
+        self.current_streak = 0;
+    }
+}
+
+/// Lifetime statistics of all the boards which were played
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct Stats {
+    boards: Vec<BoardStats>,
+}
+
+impl Stats {
+    /// Add a finished game to the statistics of its board, `assisted` if hints or undos were used
+    pub fn record(&mut self, board: ScoreBoard, is_won: bool, elapsed: Duration, assisted: bool) {
+        self.board_mut(board).record(is_won, elapsed, assisted);
+    }
+
+    /// Add a game which was left unfinished to the statistics of its board
+    pub fn record_abandoned(&mut self, board: ScoreBoard) {
+        self.board_mut(board).record_abandoned();
+    }
+
+    /// Statistics of a board, which are added if it wasn't played yet
+    fn board_mut(&mut self, board: ScoreBoard) -> &mut BoardStats {
+        let index = match self.boards.binary_search_by(|stats| stats.board.cmp(&board)) {
+            Ok(index) => index,
+            Err(index) => {
+                self.boards.insert(index, BoardStats::new(board));
+                index
+            },
+        };
+
+        &mut self.boards[index]
+    }
+
+    /// Statistics of a board, if it was played
+    pub fn board(&self, board: ScoreBoard) -> Option<&BoardStats> {
+        self.boards.iter().find(|stats| stats.board == board)
+    }
+
+    /// Statistics of all the boards which were played
+    pub fn boards(&self) -> &[BoardStats] {
+        &self.boards
+    }
+
+    /// Forget all the games played so far
+    pub fn reset(&mut self) {
+        self.boards.clear();
+    }
+
+    /// Export the statistics as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Statistics can always be serialized")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameDifficulty;
+
+    #[test]
+    fn lifetime_stats() {
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+        let hard = ScoreBoard::from(&GameDifficulty::HARD);
+        let mut stats = Stats::default();
+        assert!(stats.board(easy).is_none());
+
+        for (is_won, seconds) in [(true, 30), (false, 5), (true, 20), (true, 40), (true, 10), (false, 12)] {
+            stats.record(easy, is_won, Duration::from_secs(seconds), false);
+        }
+        stats.record(hard, true, Duration::from_millis(200_500), false);
+
+        let easy_stats = stats.board(easy).unwrap();
+        assert_eq!(easy_stats.played, 6);
+        assert_eq!(easy_stats.won, 4);
+        assert_eq!(easy_stats.win_rate(), Some(4.0 / 6.0));
+        assert_eq!(easy_stats.current_streak, 0);
+        assert_eq!(easy_stats.longest_streak, 3);
+        assert_eq!(easy_stats.average_win_time(), Some(Duration::from_secs(25)));
+        assert_eq!(easy_stats.median_win_time(), Some(Duration::from_secs(25)));
+        assert_eq!(stats.board(hard).unwrap().median_win_time(), Some(Duration::from_millis(200_500)));
+        assert_eq!(stats.board(hard).unwrap().current_streak, 1);
+
+        // Wins from 10 to 40 seconds, in buckets of 8 seconds
+        let histogram: Vec<(i32, i32, u32)> =
+            easy_stats.histogram(4).iter().map(|bucket| (bucket.from, bucket.to, bucket.count)).collect();
+        assert_eq!(histogram, vec![(10, 17, 1), (18, 25, 1), (26, 33, 1), (34, 41, 1)]);
+        assert_eq!(stats.board(hard).unwrap().histogram(10).len(), 1);
+
+        // Export and reset
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(json["boards"][0]["won"], 4);
+
+        stats.reset();
+        assert!(stats.boards().is_empty());
+    }
+
+    #[test]
+    fn abandoned_games() {
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+        let mut stats = Stats::default();
+
+        stats.record(easy, true, Duration::from_secs(30), false);
+        stats.record_abandoned(easy);
+        stats.record(easy, true, Duration::from_secs(20), false);
+
+        let easy_stats = stats.board(easy).unwrap();
+        assert_eq!((easy_stats.played, easy_stats.won, easy_stats.abandoned), (3, 2, 1));
+        assert_eq!(easy_stats.win_rate(), Some(2.0 / 3.0));
+        assert_eq!((easy_stats.current_streak, easy_stats.longest_streak), (1, 1));
+
+        // Statistics saved before abandoned games were counted
+        let json = stats.to_json().replace("\"abandoned\": 1,", "");
+        let loaded: Stats = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.board(easy).unwrap().abandoned, 0);
+    }
+
+    #[test]
+    fn assisted_games() {
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+        let mut stats = Stats::default();
+
+        // Assisted games count as played and won, but neither extend nor end the streak, and their times are left out
+        stats.record(easy, true, Duration::from_secs(30), false);
+        stats.record(easy, true, Duration::from_secs(5), true);
+        stats.record(easy, false, Duration::from_secs(8), true);
+        stats.record(easy, true, Duration::from_secs(20), false);
+
+        let easy_stats = stats.board(easy).unwrap();
+        assert_eq!((easy_stats.played, easy_stats.won, easy_stats.assisted), (4, 3, 2));
+        assert_eq!((easy_stats.current_streak, easy_stats.longest_streak), (2, 2));
+        assert_eq!(easy_stats.win_times, [30, 20].map(Duration::from_secs));
+        assert_eq!(easy_stats.average_win_time(), Some(Duration::from_secs(25)));
+
+        // Statistics saved before assisted games were counted
+        let json = stats.to_json().replace("\"assisted\": 2,", "");
+        let loaded: Stats = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.board(easy).unwrap().assisted, 0);
+    }
+
+    #[test]
+    fn whole_second_win_times() {
+        // Statistics saved before the win times were counted in milliseconds
+        let json = r#"{"boards": [{
+            "board": {"width": 10, "height": 10, "mines": 10},
+            "played": 3, "won": 2, "current_streak": 2, "longest_streak": 2,
+            "win_times": [12, 15]
+        }]}"#;
+        let mut stats: Stats = serde_json::from_str(json).unwrap();
+        let easy = ScoreBoard::from(&GameDifficulty::EASY);
+
+        stats.record(easy, true, Duration::from_millis(9_750), false);
+        let easy_stats = stats.board(easy).unwrap();
+        assert_eq!(easy_stats.win_times, [12_000, 15_000, 9_750].map(Duration::from_millis));
+        assert_eq!(easy_stats.median_win_time(), Some(Duration::from_secs(12)));
+
+        // Both kinds of win times are read back
+        let loaded: Stats = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(loaded, stats);
+    }
+
+    #[test]
+    fn no_games() {
+        let stats = BoardStats::new(ScoreBoard::from(&GameDifficulty::EASY));
+        assert_eq!(stats.win_rate(), None);
+        assert_eq!(stats.average_win_time(), None);
+        assert_eq!(stats.median_win_time(), None);
+        assert!(stats.histogram(10).is_empty());
+    }
+}